@@ -0,0 +1,382 @@
+// src-tauri/src/browse.rs
+use crate::models::{ColumnFilter, ColumnInfo, FilterOperator, TableQuery};
+use anyhow::{bail, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A `TableQuery` compiled to SQL fragments plus the values bound to their `?` placeholders
+#[derive(Debug, Default)]
+pub struct CompiledQuery {
+    /// `WHERE ...` (with a leading space) or empty
+    pub where_clause: String,
+    /// `ORDER BY ...` (with a leading space) or empty
+    pub order_by: String,
+    pub params: Vec<Value>,
+    /// The sort order as (quoted column, descending) pairs
+    pub sort: Vec<(String, bool)>,
+}
+
+/// Compile a query spec against the table's columns. Column names are checked against
+/// the table and quoted; every user-supplied value is bound as a parameter.
+pub fn compile(query: &TableQuery, columns: &[ColumnInfo]) -> anyhow::Result<CompiledQuery> {
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+
+    for filter in &query.filters {
+        let column = find_column(columns, &filter.column)?;
+        conditions.push(compile_filter(
+            filter,
+            &quote_identifier(&column.name),
+            &mut params,
+        )?);
+    }
+
+    if let Some(term) = query.search.as_deref().filter(|t| !t.is_empty()) {
+        let text_columns: Vec<&ColumnInfo> = columns.iter().filter(|c| is_text_column(c)).collect();
+
+        if text_columns.is_empty() {
+            // Nothing to search in
+            conditions.push("0".to_string());
+        } else {
+            let pattern = format!("%{}%", escape_like(term));
+            let matches: Vec<String> = text_columns
+                .iter()
+                .map(|c| {
+                    params.push(Value::Text(pattern.clone()));
+                    format!("{} LIKE ? ESCAPE '\\'", quote_identifier(&c.name))
+                })
+                .collect();
+            conditions.push(format!("({})", matches.join(" OR ")));
+        }
+    }
+
+    let mut sort = Vec::new();
+    for sort_column in &query.order_by {
+        let column = find_column(columns, &sort_column.column)?;
+        sort.push((quote_identifier(&column.name), sort_column.descending));
+    }
+
+    Ok(CompiledQuery {
+        where_clause: if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        },
+        order_by: order_by_clause(&sort),
+        params,
+        sort,
+    })
+}
+
+fn order_by_clause(sort: &[(String, bool)]) -> String {
+    if sort.is_empty() {
+        return String::new();
+    }
+    let terms: Vec<String> = sort
+        .iter()
+        .map(|(column, descending)| {
+            format!("{} {}", column, if *descending { "DESC" } else { "ASC" })
+        })
+        .collect();
+    format!(" ORDER BY {}", terms.join(", "))
+}
+
+/// Add a condition to a (possibly empty) `WHERE` clause
+pub fn and_where(where_clause: &str, condition: &str) -> String {
+    if where_clause.is_empty() {
+        format!(" WHERE {}", condition)
+    } else {
+        format!("{} AND ({})", where_clause, condition)
+    }
+}
+
+/// Keyset pagination plan: the full, unique sort order for a browse query
+pub struct Keyset {
+    /// User sort order followed by the row key, as (quoted column, descending)
+    pub sort: Vec<(String, bool)>,
+    /// Identifies the table, filters and sort order a continuation token belongs to
+    pub signature: String,
+}
+
+impl Keyset {
+    /// Plan keyset pagination for `table` (already quoted). Returns `None` when rows
+    /// can't be identified uniquely (e.g. views), in which case OFFSET paging is used.
+    pub fn plan(
+        conn: &Connection,
+        table: &str,
+        columns: &[ColumnInfo],
+        compiled: &CompiledQuery,
+    ) -> Option<Keyset> {
+        let mut sort = compiled.sort.clone();
+        for key in row_key(conn, table, columns)? {
+            if !sort.iter().any(|(column, _)| *column == key) {
+                sort.push((key, false));
+            }
+        }
+
+        Some(Keyset {
+            signature: query_signature(table, compiled),
+            sort,
+        })
+    }
+
+    pub fn order_by(&self) -> String {
+        order_by_clause(&self.sort)
+    }
+
+    /// Condition selecting the rows that sort strictly after `after` (the last row's sort
+    /// values). Expanded into ORs rather than a row value so mixed ASC/DESC and NULLs
+    /// (which SQLite sorts first) are handled.
+    pub fn after_condition(&self, after: &[Value], params: &mut Vec<Value>) -> String {
+        let mut alternatives = Vec::new();
+
+        for (i, ((column, descending), value)) in self.sort.iter().zip(after).enumerate() {
+            let mut terms = Vec::new();
+            for ((prev_column, _), prev_value) in self.sort[..i].iter().zip(after) {
+                terms.push(format!("{} IS ?", prev_column));
+                params.push(prev_value.clone());
+            }
+
+            let next = match (value, descending) {
+                (Value::Null, false) => format!("{} IS NOT NULL", column),
+                // Nothing sorts after NULL in descending order
+                (Value::Null, true) => "0".to_string(),
+                (_, false) => {
+                    params.push(value.clone());
+                    format!("{} > ?", column)
+                }
+                (_, true) => {
+                    params.push(value.clone());
+                    format!("({} < ? OR {} IS NULL)", column, column)
+                }
+            };
+            terms.push(next);
+            alternatives.push(format!("({})", terms.join(" AND ")));
+        }
+
+        alternatives.join(" OR ")
+    }
+}
+
+/// Columns that uniquely identify a row: the rowid if the table has one (and it isn't
+/// shadowed by a real column), otherwise the primary key
+fn row_key(conn: &Connection, table: &str, columns: &[ColumnInfo]) -> Option<Vec<String>> {
+    let alias = ["rowid", "_rowid_", "oid"]
+        .into_iter()
+        .find(|alias| !columns.iter().any(|c| c.name.eq_ignore_ascii_case(alias)));
+
+    if let Some(alias) = alias {
+        // Fails for WITHOUT ROWID tables and views
+        if conn
+            .prepare(&format!("SELECT {} FROM {} LIMIT 0", alias, table))
+            .is_ok()
+        {
+            return Some(vec![alias.to_string()]);
+        }
+    }
+
+    let primary_key: Vec<String> = columns
+        .iter()
+        .filter(|c| c.is_primary_key)
+        .map(|c| quote_identifier(&c.name))
+        .collect();
+    (!primary_key.is_empty()).then_some(primary_key)
+}
+
+/// Ties a continuation token to the table, filters and sort order it was issued for
+pub fn query_signature(table: &str, compiled: &CompiledQuery) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(table.as_bytes());
+    hasher.update(compiled.where_clause.as_bytes());
+    hasher.update(compiled.order_by.as_bytes());
+    hasher.update(format!("{:?}", compiled.params).as_bytes());
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// Contents of the opaque continuation token handed to the frontend
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorToken {
+    signature: String,
+    /// Sort values of the last row returned (keyset paging)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    after: Vec<serde_json::Value>,
+    /// Rows already returned (OFFSET fallback)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    offset: Option<i64>,
+}
+
+/// Where the next page starts
+pub enum PagePosition {
+    After(Vec<Value>),
+    Offset(i64),
+}
+
+pub fn encode_cursor(signature: &str, position: PagePosition) -> String {
+    let token = match position {
+        PagePosition::After(values) => CursorToken {
+            signature: signature.to_string(),
+            after: values.into_iter().map(cursor_value_to_json).collect(),
+            offset: None,
+        },
+        PagePosition::Offset(offset) => CursorToken {
+            signature: signature.to_string(),
+            after: Vec::new(),
+            offset: Some(offset),
+        },
+    };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&token).unwrap_or_default())
+}
+
+pub fn decode_cursor(cursor: &str, signature: &str) -> anyhow::Result<PagePosition> {
+    let token: CursorToken = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .context("Invalid continuation token")?;
+
+    if token.signature != signature {
+        bail!("Continuation token does not match this table, filter or sort order");
+    }
+
+    match token.offset {
+        Some(offset) => Ok(PagePosition::Offset(offset)),
+        None => Ok(PagePosition::After(
+            token
+                .after
+                .iter()
+                .map(json_to_cursor_value)
+                .collect::<anyhow::Result<_>>()?,
+        )),
+    }
+}
+
+// Cursor values must round-trip exactly, so blobs and non-finite reals get tagged objects
+fn cursor_value_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => i.into(),
+        Value::Real(f) => serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .unwrap_or_else(|| serde_json::json!({ "real": f.to_string() })),
+        Value::Text(s) => s.into(),
+        Value::Blob(b) => serde_json::json!({ "blob": URL_SAFE_NO_PAD.encode(b) }),
+    }
+}
+
+fn json_to_cursor_value(value: &serde_json::Value) -> anyhow::Result<Value> {
+    if let Some(blob) = value.get("blob").and_then(|b| b.as_str()) {
+        return Ok(Value::Blob(URL_SAFE_NO_PAD.decode(blob)?));
+    }
+    if let Some(real) = value.get("real").and_then(|r| r.as_str()) {
+        return Ok(Value::Real(real.parse()?));
+    }
+    match value {
+        // Keep integers and reals apart: a REAL 2.0 must not come back as INTEGER 2
+        serde_json::Value::Number(n) if n.is_f64() => {
+            Ok(Value::Real(n.as_f64().unwrap_or_default()))
+        }
+        _ => json_to_sql(value, "cursor"),
+    }
+}
+
+fn compile_filter(
+    filter: &ColumnFilter,
+    column: &str,
+    params: &mut Vec<Value>,
+) -> anyhow::Result<String> {
+    let comparison = |op: &str, params: &mut Vec<Value>| -> anyhow::Result<String> {
+        params.push(json_to_sql(&filter.value, &filter.column)?);
+        Ok(format!("{} {} ?", column, op))
+    };
+    let like = |prefix: &str, suffix: &str, params: &mut Vec<Value>| -> anyhow::Result<String> {
+        let text = match &filter.value {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Null => bail!("Filter on '{}' needs a value", filter.column),
+            other => other.to_string(),
+        };
+        params.push(Value::Text(format!(
+            "{}{}{}",
+            prefix,
+            escape_like(&text),
+            suffix
+        )));
+        Ok(format!("{} LIKE ? ESCAPE '\\'", column))
+    };
+
+    match filter.operator {
+        FilterOperator::Eq if filter.value.is_null() => Ok(format!("{} IS NULL", column)),
+        FilterOperator::NotEq if filter.value.is_null() => Ok(format!("{} IS NOT NULL", column)),
+        FilterOperator::Eq => comparison("=", params),
+        FilterOperator::NotEq => comparison("<>", params),
+        FilterOperator::Lt => comparison("<", params),
+        FilterOperator::Lte => comparison("<=", params),
+        FilterOperator::Gt => comparison(">", params),
+        FilterOperator::Gte => comparison(">=", params),
+        FilterOperator::Contains => like("%", "%", params),
+        FilterOperator::StartsWith => like("", "%", params),
+        FilterOperator::EndsWith => like("%", "", params),
+        FilterOperator::In => {
+            let values = filter
+                .value
+                .as_array()
+                .with_context(|| format!("Filter 'in' on '{}' needs an array", filter.column))?;
+            if values.is_empty() {
+                return Ok("0".to_string());
+            }
+            for value in values {
+                params.push(json_to_sql(value, &filter.column)?);
+            }
+            Ok(format!(
+                "{} IN ({})",
+                column,
+                vec!["?"; values.len()].join(", ")
+            ))
+        }
+        FilterOperator::IsNull => Ok(format!("{} IS NULL", column)),
+        FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", column)),
+    }
+}
+
+fn find_column<'a>(columns: &'a [ColumnInfo], name: &str) -> anyhow::Result<&'a ColumnInfo> {
+    columns
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(name))
+        .with_context(|| format!("Unknown column '{}'", name))
+}
+
+/// Columns that can hold text: TEXT affinity, or no declared type at all
+fn is_text_column(column: &ColumnInfo) -> bool {
+    let data_type = column.data_type.to_uppercase();
+    data_type.is_empty()
+        || data_type.contains("CHAR")
+        || data_type.contains("CLOB")
+        || data_type.contains("TEXT")
+}
+
+fn json_to_sql(value: &serde_json::Value, column: &str) -> anyhow::Result<Value> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        _ => bail!("Unsupported filter value for '{}': {}", column, value),
+    })
+}
+
+/// Escape `%`, `_` and the escape character itself for `LIKE ... ESCAPE '\'`
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Quote an identifier for SQLite, doubling any embedded quotes
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -7,10 +7,14 @@ use std::path::Path;
 use std::sync::Mutex;
 use std::time::Duration;
 use tauri::State;
-use tokio::time::timeout;
 
+use crate::editing::EditLog;
+use crate::integrity::{self, SignedPatchWriter};
 use crate::license::{LicenseManager, LicenseStatus};
+use crate::profiles::ProfileManager;
+use crate::rollback::RollbackWriter;
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
@@ -18,7 +22,8 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 // ADD THIS TYPE ALIAS AFTER YOUR IMPORTS
 type LicenseManagerState = Mutex<LicenseManager>;
-type DbManager = Mutex<DatabaseManager>;
+type DbManager = DatabaseManager;
+type ProfileManagerState = Mutex<ProfileManager>;
 
 #[derive(serde::Serialize)]
 pub struct TrialInfo {
@@ -29,24 +34,46 @@ pub struct TrialInfo {
 
 #[tauri::command]
 pub async fn connect_database(
-    db_manager: State<'_, Mutex<DatabaseManager>>,
+    db_manager: State<'_, DbManager>,
+    profile_manager: State<'_, ProfileManagerState>,
     path: String,
     password: String,
     settings: Option<serde_json::Value>, // Add this parameter
+    alias: Option<String>,
+    access_mode: Option<AccessMode>, // Defaults to read-only
 ) -> Result<DatabaseInfo, String> {
-    let mut manager = db_manager.lock().unwrap();
+    // Fall back to the saved profile for this path (alias and cipher settings)
+    let profile = {
+        let profiles = profile_manager.lock().unwrap();
+        profiles.find_by_path(&path).cloned()
+    };
+
+    let manager = db_manager.inner().clone();
 
     // Parse settings if provided
     let sqlcipher_settings = if let Some(s) = settings {
         Some(s)
     } else {
-        None
+        profile.as_ref().and_then(|p| p.cipher_settings.clone())
     };
+    let access_mode = access_mode
+        .or_else(|| profile.as_ref().map(|p| p.access_mode))
+        .unwrap_or_default();
+    let alias = alias.or_else(|| profile.and_then(|p| p.alias));
+
+    // Key derivation can take a while - keep it off the async runtime
+    let connect_path = path.clone();
+    let result = tokio::task::spawn_blocking({
+        let manager = manager.clone();
+        move || manager.connect_database(&connect_path, &password, sqlcipher_settings, access_mode)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
 
-    match manager.connect_database(&path, &password, sqlcipher_settings) {
+    match result {
         Ok(db_info) => {
             println!("✅ Connected to database: {}", path);
-            Ok(db_info)
+            Ok(manager.set_alias(&path, alias).unwrap_or(db_info))
         }
         Err(e) => {
             println!("❌ Failed to connect: {}", e);
@@ -56,42 +83,71 @@ pub async fn connect_database(
 }
 
 #[tauri::command]
-pub async fn get_table_info(db_path: String, table_name: String) -> Result<TableInfo, String> {
-    use rusqlite::Connection;
+pub async fn disconnect_database(
+    path: String,
+    db_manager: State<'_, DbManager>,
+    edit_log: State<'_, EditLog>,
+) -> Result<(), String> {
+    match db_manager.close(&path) {
+        Ok(()) => {
+            edit_log.clear(&path);
+            println!("🔌 Disconnected database: {}", path);
+            Ok(())
+        }
+        Err(e) => {
+            println!("❌ Failed to disconnect: {}", e);
+            Err(format!("Disconnect failed: {}", e))
+        }
+    }
+}
 
-    let conn = Connection::open(&db_path).map_err(|e| format!("Failed to open database: {}", e))?;
+#[tauri::command]
+pub async fn list_connections(
+    db_manager: State<'_, DbManager>,
+) -> Result<Vec<DatabaseInfo>, String> {
+    Ok(db_manager.list_connections())
+}
 
-    let mut stmt = conn
-        .prepare(&format!("PRAGMA table_info({})", table_name))
-        .map_err(|e| format!("Failed to get table info: {}", e))?;
+/// Check connected databases for external changes right away instead of waiting for the watcher
+#[tauri::command]
+pub async fn check_database_changes(
+    db_manager: State<'_, DbManager>,
+) -> Result<Vec<DatabaseChangeEvent>, String> {
+    Ok(db_manager.check_for_changes())
+}
 
-    let mut columns = Vec::new();
-    let rows = stmt
-        .query_map([], |row| {
-            Ok(ColumnInfo {
-                name: row.get(1)?,
-                data_type: row.get(2)?,
-                is_nullable: row.get::<_, i32>(3)? == 0,
-                default_value: row.get(4).ok(),
-                is_primary_key: row.get::<_, i32>(5)? == 1,
-            })
-        })
-        .map_err(|e| format!("Query failed: {}", e))?;
+#[tauri::command]
+pub async fn get_table_info(
+    db_path: String,
+    table_name: String,
+    manager: State<'_, DbManager>,
+) -> Result<TableInfo, String> {
+    let conn = manager.reader(&db_path).map_err(|e| e.to_string())?;
 
-    for row in rows {
-        columns.push(row.map_err(|e| format!("Row error: {}", e))?);
-    }
+    let columns = manager
+        .get_table_columns(&conn, &table_name)
+        .map_err(|e| format!("Failed to get table info: {}", e))?;
 
     let row_count: i64 = conn
-        .query_row(&format!("SELECT COUNT(*) FROM {}", table_name), [], |row| {
-            row.get(0)
-        })
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {}",
+                crate::browse::quote_identifier(&table_name)
+            ),
+            [],
+            |row| row.get(0),
+        )
         .unwrap_or(0);
 
+    let create_sql = get_create_table_sql(&conn, &table_name).unwrap_or_default();
+    let (options, virtual_module) = crate::database::table_options(&create_sql);
+
     Ok(TableInfo {
         name: table_name,
         row_count,
         columns,
+        options,
+        virtual_module,
     })
 }
 
@@ -100,9 +156,13 @@ pub async fn get_database_tables(
     db_path: String,
     manager: State<'_, DbManager>,
 ) -> Result<Vec<TableInfo>, String> {
-    let db_manager = manager.lock().unwrap();
+    let db_manager = manager.inner().clone();
+    let path = db_path.clone();
+    let result = tokio::task::spawn_blocking(move || db_manager.get_tables(&path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
 
-    match db_manager.get_tables(&db_path) {
+    match result {
         Ok(tables) => {
             println!("Retrieved {} tables from {}", tables.len(), db_path);
             Ok(tables)
@@ -120,72 +180,433 @@ pub async fn get_table_data(
     table_name: String,
     limit: Option<i64>,
     offset: Option<i64>, // ← NEW PARAMETER
+    query: Option<TableQuery>,
     manager: State<'_, DbManager>,
 ) -> Result<TableData, String> {
-    // Add timeout for large queries (60 seconds)
+    // Large queries are interrupted after 60 seconds
     let timeout_duration = Duration::from_secs(60);
 
-    let result = timeout(timeout_duration, async {
-        let db_manager = manager.lock().unwrap();
+    // Log the request with more details
+    println!(
+        "Fetching table '{}' from '{}' with limit: {:?}, offset: {:?}",
+        table_name, db_path, limit, offset
+    );
 
-        // Log the request with more details
-        println!(
-            "Fetching table '{}' from '{}' with limit: {:?}, offset: {:?}",
-            table_name, db_path, limit, offset
-        );
+    // Check installation status for trial expiration
+    let is_expired = check_installation_status().unwrap_or(false);
 
-        // Check installation status for trial expiration
-        let is_expired = check_installation_status().unwrap_or(false);
-
-        // Enforce 2-row limit if expired
-        let (effective_limit, effective_offset) = if is_expired {
-            println!("Trial expired - enforcing 2 row limit for table data");
-            (Some(2), Some(0)) // Force limit 2, offset 0
-        } else {
-            (limit, offset)
-        };
+    // Enforce 2-row limit if expired
+    let (effective_limit, effective_offset) = if is_expired {
+        println!("Trial expired - enforcing 2 row limit for table data");
+        (Some(2), Some(0)) // Force limit 2, offset 0
+    } else {
+        (limit, offset)
+    };
 
-        db_manager.get_table_data(&db_path, &table_name, effective_limit, effective_offset)
+    // Runs on a pooled read-only connection, so other databases stay responsive. The
+    // query is interrupted inside the blocking task, so the connection is free again
+    // when the timeout is reported.
+    let db_manager = manager.inner().clone();
+    let (path, table) = (db_path.clone(), table_name.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        db_manager.get_table_data(
+            &path,
+            &table,
+            query.as_ref(),
+            effective_limit,
+            effective_offset,
+            timeout_duration,
+        )
     })
-    .await;
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
 
     match result {
-        Ok(data_result) => match data_result {
-            Ok(data) => {
-                println!(
-                    "Successfully retrieved {} rows from table '{}' in '{}'",
-                    data.rows.len(),
-                    table_name,
-                    db_path
-                );
-                Ok(data)
-            }
-            Err(e) => {
-                println!("Database error for table '{}': {}", table_name, e);
-                Err(format!("Database error: {}", e))
-            }
-        },
-        Err(_) => {
+        Ok(data) => {
             println!(
-                "Timeout occurred while fetching table '{}' (limit: {:?}, offset: {:?})",
-                table_name, limit, offset
+                "Successfully retrieved {} rows from table '{}' in '{}'",
+                data.rows.len(),
+                table_name,
+                db_path
             );
-            Err(format!(
-                "Query timeout - table '{}' took too long to fetch. Try using a smaller row limit.",
-                table_name
-            ))
+            Ok(data)
+        }
+        Err(e) => {
+            println!(
+                "Database error for table '{}' (limit: {:?}, offset: {:?}): {}",
+                table_name, limit, offset, e
+            );
+            Err(format!("Database error: {}", e))
+        }
+    }
+}
+
+/// Cursor-paginated browsing; pass the returned `next_cursor` back to get the next page
+#[tauri::command]
+pub async fn browse_table_data(
+    db_path: String,
+    table_name: String,
+    query: Option<TableQuery>,
+    page_size: Option<i64>,
+    cursor: Option<String>,
+    manager: State<'_, DbManager>,
+) -> Result<TablePage, String> {
+    let timeout_duration = Duration::from_secs(60);
+
+    // Same trial restriction as get_table_data: first 2 rows only, no further pages
+    let is_expired = check_installation_status().unwrap_or(false);
+    if is_expired && cursor.is_some() {
+        return Err("Trial expired - only the first rows are available".to_string());
+    }
+    let page_size = if is_expired {
+        2
+    } else {
+        page_size.unwrap_or(100).clamp(1, 10_000)
+    };
+
+    // Interrupted inside the blocking task, like get_table_data
+    let db_manager = manager.inner().clone();
+    let table = table_name.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        db_manager.browse_table_data(
+            &db_path,
+            &table,
+            query.as_ref(),
+            page_size,
+            cursor.as_deref(),
+            timeout_duration,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    match result {
+        Ok(mut page) => {
+            if is_expired {
+                page.next_cursor = None;
+            }
+            Ok(page)
+        }
+        Err(e) => {
+            println!("Database error for table '{}': {}", table_name, e);
+            Err(format!("Database error: {}", e))
+        }
+    }
+}
+
+/// SQL console: run a read-only query on a connected database
+#[tauri::command]
+pub async fn execute_query(
+    db_path: String,
+    sql: String,
+    max_rows: Option<usize>,
+    timeout_secs: Option<u64>,
+    explain: Option<bool>,
+    manager: State<'_, DbManager>,
+) -> Result<QueryResult, String> {
+    // Same trial restriction as get_table_data
+    let is_expired = check_installation_status().unwrap_or(false);
+    let max_rows = if is_expired {
+        println!("Trial expired - enforcing 2 row limit for query results");
+        2
+    } else {
+        max_rows.unwrap_or(1000).min(100_000)
+    };
+    let timeout_duration = Duration::from_secs(timeout_secs.unwrap_or(30).clamp(1, 600));
+
+    println!("Running query on '{}' (max {} rows)", db_path, max_rows);
+
+    // The query is interrupted inside the blocking task, so no outer timeout is needed
+    let db_manager = manager.inner().clone();
+    let path = db_path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        db_manager.execute_query(
+            &path,
+            &sql,
+            max_rows,
+            timeout_duration,
+            explain.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    result.map_err(|e| {
+        println!("Query failed on '{}': {}", db_path, e);
+        format!("Query failed: {}", e)
+    })
+}
+
+/// Export a table (optionally filtered/sorted) or a query result to CSV, JSON Lines or Parquet
+#[tauri::command]
+pub async fn export_table_data(
+    db_path: String,
+    source: ExportSource,
+    format: ExportFormat,
+    output_path: String,
+    options: Option<ExportOptions>,
+    manager: State<'_, DbManager>,
+) -> Result<ExportResult, String> {
+    crate::export::check_output_path(&output_path)?;
+
+    let mut options = options.unwrap_or_default();
+    // Same trial restriction as get_table_data
+    if check_installation_status().unwrap_or(false) {
+        println!("Trial expired - enforcing 2 row limit for export");
+        options.max_rows = Some(2);
+    }
+
+    let db_manager = manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        crate::export::export(
+            &db_manager,
+            &db_path,
+            &source,
+            format,
+            &output_path,
+            &options,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Export failed: {}", e))
+}
+
+/// Import a CSV or JSON Lines file into a table, optionally creating it
+#[tauri::command]
+pub async fn import_table_data(
+    db_path: String,
+    table_name: String,
+    file_path: String,
+    format: ImportFormat,
+    options: Option<ImportOptions>,
+    manager: State<'_, DbManager>,
+) -> Result<ImportResult, String> {
+    let mut options = options.unwrap_or_default();
+    // Same trial restriction as get_table_data
+    if check_installation_status().unwrap_or(false) {
+        println!("Trial expired - enforcing 2 row limit for import");
+        options.max_rows = Some(2);
+    }
+
+    let db_manager = manager.inner().clone();
+    let result = tokio::task::spawn_blocking(move || {
+        crate::importer::import(
+            &db_manager,
+            &db_path,
+            &table_name,
+            &file_path,
+            format,
+            &options,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    // {:#} keeps the context chain ("stopped at row ...: <cause>")
+    result.map_err(|e| format!("Import failed: {:#}", e))
+}
+
+/// Save the schema of a connected database, optionally with a hash of every row, as a
+/// JSON snapshot. A snapshot path (`.json`) can be passed as either side of
+/// `compare_database_schemas` and `compare_table_data_fast`.
+#[tauri::command]
+pub async fn save_schema_snapshot(
+    db_path: String,
+    output_path: String,
+    include_row_hashes: Option<bool>,
+    manager: State<'_, DbManager>,
+) -> Result<SnapshotResult, String> {
+    crate::export::check_output_path(&output_path)?;
+    if !crate::snapshot::is_snapshot_path(&output_path) {
+        return Err("Snapshot files must have a .json extension".to_string());
+    }
+
+    let db_manager = manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        crate::snapshot::save_snapshot(
+            &db_manager,
+            &db_path,
+            &output_path,
+            include_row_hashes.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Snapshot failed: {:#}", e))
+}
+
+/// Write schema and (optionally) data of a connected database as a replayable SQL script
+#[tauri::command]
+pub async fn dump_database(
+    db_path: String,
+    output_path: String,
+    include_data: Option<bool>,
+    manager: State<'_, DbManager>,
+) -> Result<DumpResult, String> {
+    crate::export::check_output_path(&output_path)?;
+
+    // Same trial restriction as get_table_data, per table
+    let max_rows_per_table = if check_installation_status().unwrap_or(false) {
+        println!("Trial expired - enforcing 2 row limit for dump");
+        Some(2)
+    } else {
+        None
+    };
+
+    let db_manager = manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        crate::dump::dump(
+            &db_manager,
+            &db_path,
+            &output_path,
+            include_data.unwrap_or(true),
+            max_rows_per_table,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Dump failed: {:#}", e))
+}
+
+/// Three-way merge: bring the changes `theirs` made since `base` into `ours`. Writes a
+/// patch for ours (to a temp file unless `output_path` is given) and lists the conflicts.
+#[tauri::command]
+pub async fn merge_databases(
+    base_path: String,
+    ours_path: String,
+    theirs_path: String,
+    key_columns: Option<HashMap<String, String>>,
+    filter: Option<ComparisonFilter>,
+    output_path: Option<String>,
+    manager: State<'_, DbManager>,
+) -> Result<MergeResult, String> {
+    let output_path = match output_path {
+        Some(path) => {
+            crate::export::check_output_path(&path)?;
+            path
+        }
+        None => {
+            let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+            std::env::temp_dir()
+                .join(format!("plandb_merge_{}.sql", timestamp))
+                .to_string_lossy()
+                .to_string()
         }
+    };
+
+    let db_manager = manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        crate::merge::merge(
+            &db_manager,
+            &base_path,
+            &ours_path,
+            &theirs_path,
+            &key_columns.unwrap_or_default(),
+            &filter.unwrap_or_default(),
+            &output_path,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Merge failed: {:#}", e))
+}
+
+/// Sync both ways: one patch per database with the rows only the other side has, and
+/// differing rows resolved by the conflict policy
+#[tauri::command]
+pub async fn sync_databases(
+    db1_path: String,
+    db2_path: String,
+    options: Option<SyncOptions>,
+    filter: Option<ComparisonFilter>,
+    manager: State<'_, DbManager>,
+) -> Result<SyncResult, String> {
+    let options = options.unwrap_or_default();
+    let filter = filter.unwrap_or_default();
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+    let temp_dir = std::env::temp_dir();
+    let source_patch = temp_dir
+        .join(format!("plandb_sync_{}_source.sql", timestamp))
+        .to_string_lossy()
+        .to_string();
+    let target_patch = temp_dir
+        .join(format!("plandb_sync_{}_target.sql", timestamp))
+        .to_string_lossy()
+        .to_string();
+
+    let db_manager = manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        crate::sync::sync(
+            &db_manager,
+            &db1_path,
+            &db2_path,
+            &options,
+            &filter,
+            &source_patch,
+            &target_patch,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Sync failed: {:#}", e))
+}
+
+/// Write a comparison of two databases (schema changes, row statistics, sample differing
+/// rows) as a self-contained HTML page, Markdown or versioned JSON
+#[tauri::command]
+pub async fn generate_comparison_report(
+    db1_path: String,
+    db2_path: String,
+    format: ReportFormat,
+    output_path: String,
+    options: Option<ReportOptions>,
+    manager: State<'_, DbManager>,
+) -> Result<ReportResult, String> {
+    crate::export::check_output_path(&output_path)?;
+
+    let mut options = options.unwrap_or_default();
+    // Same trial restriction as get_table_data, for the sampled rows
+    if check_installation_status().unwrap_or(false) {
+        println!("Trial expired - enforcing 2 row limit for report samples");
+        options.max_samples = Some(2);
     }
+
+    let db_manager = manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        crate::report::generate_report(
+            &db_manager,
+            &db1_path,
+            &db2_path,
+            format,
+            &output_path,
+            &options,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Report failed: {:#}", e))
 }
+
 #[tauri::command]
 pub async fn compare_database_schemas(
     db1_path: String,
     db2_path: String,
+    filter: Option<ComparisonFilter>,
+    rules: Option<ComparisonRules>,
     manager: State<'_, DbManager>,
 ) -> Result<SchemaComparison, String> {
-    let db_manager = manager.lock().unwrap();
+    let db_manager = manager.inner().clone();
+    let (path1, path2) = (db1_path.clone(), db2_path.clone());
+    let filter = filter.unwrap_or_default();
+    let rules = rules.unwrap_or_default();
+    let result = tokio::task::spawn_blocking(move || {
+        db_manager.compare_schemas(&path1, &path2, &filter, &rules)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
 
-    match db_manager.compare_schemas(&db1_path, &db2_path) {
+    match result {
         Ok(comparison) => {
             println!(
                 "Schema comparison completed between {} and {}",
@@ -206,13 +627,27 @@ pub async fn compare_table_data_fast(
     db2_path: String,
     table_name: String,
     primary_key: String,
+    filter: Option<ComparisonFilter>,
     manager: State<'_, DbManager>,
 ) -> Result<DataComparisonResult, String> {
-    let db_manager = manager.lock().unwrap();
-
     println!("Fast comparing table '{}' between databases", table_name);
 
-    match db_manager.compare_table_data_fast(&db1_path, &db2_path, &table_name, &primary_key) {
+    if !filter.unwrap_or_default().includes_table(&table_name) {
+        return Err(format!(
+            "Table '{}' is excluded by the comparison filter",
+            table_name
+        ));
+    }
+
+    let db_manager = manager.inner().clone();
+    let table = table_name.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        db_manager.compare_table_data_fast(&db1_path, &db2_path, &table, &primary_key)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    match result {
         Ok(result) => {
             println!(
                 "Data comparison completed: {} total rows",
@@ -227,6 +662,60 @@ pub async fn compare_table_data_fast(
     }
 }
 
+/// Row-by-row comparison of a table, matched on `key_column`. Values equal under
+/// `rules` (e.g. `1` and `1.0`, or text differing in surrounding whitespace) and columns
+/// the filter ignores don't make a row different.
+#[tauri::command]
+pub async fn compare_table_rows(
+    db1_path: String,
+    db2_path: String,
+    table_name: String,
+    key_column: String,
+    filter: Option<ComparisonFilter>,
+    rules: Option<ComparisonRules>,
+    manager: State<'_, DbManager>,
+) -> Result<TableRowComparison, String> {
+    println!("Comparing rows of '{}' on '{}'", table_name, key_column);
+
+    // Same trial restriction as get_table_data: first 2 rows only
+    let limit = check_installation_status().unwrap_or(false).then_some(2);
+
+    let db_manager = manager.inner().clone();
+    let filter = filter.unwrap_or_default();
+    let rules = rules.unwrap_or_default();
+    let result = tokio::task::spawn_blocking(move || {
+        crate::rowdiff::compare_table_rows(
+            &db_manager,
+            &db1_path,
+            &db2_path,
+            &table_name,
+            &key_column,
+            &filter,
+            &rules,
+            limit,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    match result {
+        Ok(result) => {
+            println!(
+                "✅ Rows compared: {} identical, {} different, {} missing, {} extra",
+                result.identical_rows.len(),
+                result.different_rows.len(),
+                result.missing_in_target.len(),
+                result.extra_in_target.len()
+            );
+            Ok(result)
+        }
+        Err(e) => {
+            println!("Failed to compare rows: {}", e);
+            Err(format!("Data comparison failed: {}", e))
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn test_connection() -> Result<String, String> {
     Ok("Tauri backend is working!".to_string())
@@ -237,39 +726,117 @@ pub async fn generate_schema_patch(
     db1_path: String,
     db2_path: String,
     direction: Option<String>, // "source_to_target" (default) or "target_to_source"
-    db_manager: State<'_, Mutex<DatabaseManager>>,
+    filter: Option<ComparisonFilter>,
+    rules: Option<ComparisonRules>,
+    db_manager: State<'_, DbManager>,
 ) -> Result<String, String> {
-    let manager = db_manager.lock().unwrap();
+    build_schema_patch(
+        db_manager.inner(),
+        &db1_path,
+        &db2_path,
+        direction.as_deref() == Some("target_to_source"),
+        &filter.unwrap_or_default(),
+        &rules.unwrap_or_default(),
+        false,
+    )
+}
 
+#[tauri::command]
+pub async fn generate_reversible_schema_patch(
+    db1_path: String,
+    db2_path: String,
+    direction: Option<String>,
+    filter: Option<ComparisonFilter>,
+    rules: Option<ComparisonRules>,
+    db_manager: State<'_, DbManager>,
+) -> Result<ReversibleSchemaPatch, String> {
+    let manager = db_manager.inner().clone();
+    let is_reverse = direction.as_deref() == Some("target_to_source");
+    let filter = filter.unwrap_or_default();
+    let rules = rules.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || {
+        let forward = build_schema_patch(
+            &manager, &db1_path, &db2_path, is_reverse, &filter, &rules, false,
+        )?;
+        // Generated from the opposite side, before the forward patch changes anything
+        let rollback = build_schema_patch(
+            &manager,
+            &db1_path,
+            &db2_path,
+            !is_reverse,
+            &filter,
+            &rules,
+            true,
+        )?;
+        Ok(ReversibleSchemaPatch { forward, rollback })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+// Build a schema patch. A `rollback` patch undoes the patch of the opposite direction,
+// so it is applied to its own template (the database that patch was generated for).
+fn build_schema_patch(
+    manager: &DatabaseManager,
+    db1_path: &str,
+    db2_path: &str,
+    is_reverse: bool,
+    filter: &ComparisonFilter,
+    rules: &ComparisonRules,
+    rollback: bool,
+) -> Result<String, String> {
     let comparison = manager
-        .compare_schemas(&db1_path, &db2_path)
+        .compare_schemas(db1_path, db2_path, filter, rules)
         .map_err(|e| e.to_string())?;
 
-    // Determine direction
-    let is_reverse = direction.as_deref() == Some("target_to_source");
+    // The schema the patch expects to find, checked before it is applied
+    let (template, apply_to) = if is_reverse {
+        (db2_path, db1_path)
+    } else {
+        (db1_path, db2_path)
+    };
+    let mut header = integrity::new_header(manager, template, apply_to, filter, rules)
+        .map_err(|e| e.to_string())?;
+    if rollback {
+        // Its target only exists once the forward patch has been applied, and its
+        // template is the state that patch replaces
+        header.target_schema = None;
+        header.template = None;
+    }
 
-    // Open BOTH databases to get schemas
-    let source_conn = Connection::open(&db1_path)
+    // Open BOTH databases (read-only, already unlocked) to get schemas
+    let source_conn = manager
+        .reader(db1_path)
         .map_err(|e| format!("Failed to open source database: {}", e))?;
 
-    let target_conn = Connection::open(&db2_path)
+    let target_conn = manager
+        .reader(db2_path)
         .map_err(|e| format!("Failed to open target database: {}", e))?;
 
-    // Get passwords and unlock if needed
-    let db1_password = manager.get_password(&db1_path).unwrap_or_default();
-    let db2_password = manager.get_password(&db2_path).unwrap_or_default();
-
-    unlock_database(&source_conn, &db1_password)?;
-    unlock_database(&target_conn, &db2_password)?;
-
     // Generate the SQL patch
     let mut sql = String::new();
-    sql.push_str("-- Schema Migration Patch\n");
-    if is_reverse {
+    if rollback {
+        let (template, label, undone) = if is_reverse {
+            (&comparison.database2, "Target", "Source → Target (Forward)")
+        } else {
+            (&comparison.database1, "Source", "Target → Source (Reverse)")
+        };
+        sql.push_str("-- Schema Rollback Patch\n");
+        sql.push_str(&format!("-- Direction: Rollback of {}\n", undone));
+        sql.push_str(&format!("-- Template: {} ({})\n", template, label));
+        sql.push_str(&format!(
+            "-- Apply to: {} ({}) after the forward patch\n",
+            template, label
+        ));
+        sql.push_str("-- NOTE: Tables and columns the forward patch dropped come back empty.\n");
+    } else if is_reverse {
+        sql.push_str("-- Schema Migration Patch\n");
         sql.push_str("-- Direction: Target → Source (Reverse)\n");
         sql.push_str(&format!("-- Template: {} (Target)\n", comparison.database2));
         sql.push_str(&format!("-- Apply to: {} (Source)\n", comparison.database1));
     } else {
+        sql.push_str("-- Schema Migration Patch\n");
         sql.push_str("-- Direction: Source → Target (Forward)\n");
         sql.push_str(&format!("-- Template: {} (Source)\n", comparison.database1));
         sql.push_str(&format!("-- Apply to: {} (Target)\n", comparison.database2));
@@ -283,8 +850,17 @@ pub async fn generate_schema_patch(
         !m.removed_columns.is_empty()
             || !m.added_columns.is_empty()
             || !m.modified_columns.is_empty()
+            || m.column_order_changed
+            || !m.option_changes.is_empty()
     });
 
+    // Tables the patch creates or recreates, whose full-text indexes need rebuilding
+    let mut touched_tables: Vec<String> = if is_reverse {
+        comparison.added_tables.clone()
+    } else {
+        comparison.removed_tables.clone()
+    };
+
     // Start transaction only for simple operations (no PRAGMA needed)
     if !has_table_recreation {
         sql.push_str("BEGIN TRANSACTION;\n\n");
@@ -346,10 +922,18 @@ pub async fn generate_schema_patch(
 
         if is_reverse {
             // REVERSE: Make SOURCE match TARGET
-            let needs_recreation =
-                !modified.removed_columns.is_empty() || !modified.modified_columns.is_empty();
+            let needs_recreation = !modified.removed_columns.is_empty()
+                || !modified.modified_columns.is_empty()
+                || modified.column_order_changed
+                || needs_table_rebuild(
+                    &source_conn,
+                    &modified.table_name,
+                    modified,
+                    &modified.added_columns,
+                );
 
             if needs_recreation {
+                touched_tables.push(modified.table_name.clone());
                 sql.push_str(&format!(
                     "-- Recreate table: {} (reverse direction)\n",
                     modified.table_name
@@ -401,10 +985,23 @@ pub async fn generate_schema_patch(
             }
         } else {
             // FORWARD: Make TARGET match SOURCE (original behavior)
-            let needs_recreation =
-                !modified.added_columns.is_empty() || !modified.modified_columns.is_empty();
+            let columns_to_add: Vec<ColumnInfo> = modified
+                .removed_columns
+                .iter()
+                .filter_map(|col| get_column_info(&source_conn, &modified.table_name, col).ok())
+                .collect();
+            let needs_recreation = !modified.added_columns.is_empty()
+                || !modified.modified_columns.is_empty()
+                || modified.column_order_changed
+                || needs_table_rebuild(
+                    &target_conn,
+                    &modified.table_name,
+                    modified,
+                    &columns_to_add,
+                );
 
             if needs_recreation {
+                touched_tables.push(modified.table_name.clone());
                 sql.push_str(&format!(
                     "-- Recreate table: {} (columns removed/modified)\n",
                     modified.table_name
@@ -475,6 +1072,13 @@ pub async fn generate_schema_patch(
         }
     }
 
+    let template_conn = if is_reverse {
+        &target_conn
+    } else {
+        &source_conn
+    };
+    sql.push_str(&fts_rebuild_sql(template_conn, &touched_tables)?);
+
     // Close transaction only if we started one
     if !has_table_recreation {
         sql.push_str("COMMIT;\n");
@@ -482,23 +1086,64 @@ pub async fn generate_schema_patch(
 
     sql.push_str("\n-- Migration complete\n");
 
-    Ok(sql)
+    Ok(integrity::sign(header, &sql))
 }
 
 #[tauri::command]
 pub async fn apply_patch_file(
     target_db_path: String,
     patch_file_path: String,
-    db_manager: State<'_, Mutex<DatabaseManager>>,
+    track_migration: Option<bool>,
+    force: Option<bool>, // Apply even if the integrity header doesn't match
+    verify_data: Option<bool>,
+    db_manager: State<'_, DbManager>,
 ) -> Result<String, String> {
     // Read the file content
     let patch_sql = std::fs::read_to_string(&patch_file_path)
         .map_err(|e| format!("Failed to read patch file: {}", e))?;
 
+    // Check the patch wasn't edited since it was generated
+    let modified = integrity::verify_body(&patch_sql).map_err(|e| e.to_string())?;
+    let modified = accept_mismatch(modified, force)?;
+
     // Reuse the existing apply logic
     // We can just call apply_schema_patch since we have the content now
     // and loading 100-200MB into RAM on backend is fine (unlike frontend)
-    apply_schema_patch(target_db_path, patch_sql, db_manager).await
+    let message = apply_schema_patch(
+        target_db_path,
+        patch_sql,
+        track_migration,
+        force,
+        verify_data,
+        db_manager,
+    )
+    .await?;
+    Ok(applied_despite(message, modified))
+}
+
+// A patch that fails verification is refused unless forced; a forced mismatch is
+// logged and handed back for the result message
+fn accept_mismatch(
+    mismatch: Option<String>,
+    force: Option<bool>,
+) -> Result<Option<String>, String> {
+    match mismatch {
+        Some(mismatch) if !force.unwrap_or(false) => {
+            Err(format!("Patch verification failed: {}", mismatch))
+        }
+        Some(mismatch) => {
+            println!("⚠️ Applying anyway: {}", mismatch);
+            Ok(Some(mismatch))
+        }
+        None => Ok(None),
+    }
+}
+
+fn applied_despite(message: String, mismatch: Option<String>) -> String {
+    match mismatch {
+        Some(mismatch) => format!("{}\nApplied despite: {}", message, mismatch),
+        None => message,
+    }
 }
 
 #[tauri::command]
@@ -660,23 +1305,192 @@ pub fn get_trial_info() -> Result<TrialInfo, String> {
 pub async fn apply_schema_patch(
     target_db_path: String,
     patch_sql: String,
-    db_manager: State<'_, Mutex<DatabaseManager>>,
+    track_migration: Option<bool>,
+    force: Option<bool>, // Apply even if the target's schema has changed since generation
+    verify_data: Option<bool>, // Also compare row hashes with the template afterwards
+    db_manager: State<'_, DbManager>,
 ) -> Result<String, String> {
-    const BATCH_SIZE: usize = 500; // Commit every 500 statements to prevent long locks
+    // Open target database with a new (writable) connection
+    let mut conn = open_for_apply(&db_manager, &target_db_path)?;
+
+    // Nothing may run against a schema the patch wasn't generated for
+    let drift = integrity::check_schema(&db_manager, &patch_sql, &conn)
+        .map_err(|e| format!("Failed to check target schema: {}", e))?;
+    let drift = accept_mismatch(drift, force)?;
+
+    // Once a database tracks its migrations, every patch applied to it is recorded
+    let tracked = crate::migrations::is_tracked(&conn)
+        .map_err(|e| format!("Failed to read migration history: {}", e))?;
+    let mut record = if tracked || track_migration.unwrap_or(false) {
+        let record = crate::migrations::new_record(&patch_sql);
+        let previous = crate::migrations::find_applied(&conn, &record.patch_hash)
+            .map_err(|e| format!("Failed to read migration history: {}", e))?;
+        if let Some(previous) = previous {
+            return Err(format!(
+                "This patch was already applied to {} on {}",
+                target_db_path, previous.applied_at
+            ));
+        }
+        Some(record)
+    } else {
+        None
+    };
 
-    let db_password = {
-        let manager = db_manager.lock().unwrap();
-        manager.get_password(&target_db_path).unwrap_or_default()
+    let single_transaction = record.is_some();
+    let record_migration = |conn: &Connection, executed: usize| match record.as_mut() {
+        Some(record) => {
+            record.statement_count = executed as i64;
+            crate::migrations::record(conn, record)
+                .map_err(|e| format!("Failed to record migration: {}", e))
+        }
+        None => Ok(()),
     };
+    let executed =
+        execute_schema_patch(&mut conn, &patch_sql, single_transaction, record_migration).await?;
 
-    // Open target database with a new connection
-    let conn = Connection::open(&target_db_path)
-        .map_err(|e| format!("Failed to open target database: {}", e))?;
+    // Close the connection to flush changes
+    drop(conn);
 
-    // Unlock if needed
-    unlock_database(&conn, &db_password)?;
+    // Force DatabaseManager to reconnect to see the updated schema
+    // (reuses the password and cipher settings the database was connected with)
+    if let Err(e) = db_manager.reconnect(&target_db_path) {
+        println!("⚠️ Failed to reconnect {}: {}", target_db_path, e);
+    }
 
-    // Prepare statements - filter and clean first
+    let message = format!(
+        "Schema patch applied successfully. Executed {} statements.",
+        executed
+    );
+    let mut message = applied_despite(message, drift);
+
+    // Check the result against the template the patch was generated from
+    let header = integrity::split_header(&patch_sql)
+        .ok()
+        .flatten()
+        .map(|(header, _)| header)
+        .unwrap_or_default();
+    if let Some(template) = &header.template {
+        let verification = integrity::verify_applied(
+            &db_manager,
+            template,
+            &target_db_path,
+            &header.filter,
+            &header.rules,
+            verify_data.unwrap_or(false),
+        );
+        let summary = match verification {
+            Ok(verification) => verification.summary(),
+            Err(e) => format!("Could not verify the result against {}: {}", template, e),
+        };
+        println!("🔍 {}", summary);
+        message.push('\n');
+        message.push_str(&summary);
+    }
+    Ok(message)
+}
+
+/// Apply the numbered `.sql` files of a directory (`001_init.sql`, `002_...`) in order,
+/// skipping those at or below the database's `PRAGMA user_version`. Each file runs as a
+/// tracked migration and sets `user_version` to its number when it commits.
+#[tauri::command]
+pub async fn apply_migration_directory(
+    target_db_path: String,
+    directory: String,
+    db_manager: State<'_, DbManager>,
+) -> Result<MigrationRunResult, String> {
+    let files = crate::migrations::migration_files(Path::new(&directory))
+        .map_err(|e| format!("{:#}", e))?;
+    let mut conn = open_for_apply(&db_manager, &target_db_path)?;
+
+    let start_version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read user_version: {}", e))?;
+    let mut result = MigrationRunResult {
+        applied: Vec::new(),
+        skipped: Vec::new(),
+        user_version: start_version,
+    };
+
+    for (version, path) in files {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if version <= start_version {
+            result.skipped.push(file_name);
+            continue;
+        }
+
+        let patch_sql = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", file_name, e))?;
+        let mut record = crate::migrations::new_record(&patch_sql);
+        record.name = Some(file_name.clone());
+        record.version = Some(version);
+        let previous = crate::migrations::find_applied(&conn, &record.patch_hash)
+            .map_err(|e| format!("Failed to read migration history: {}", e))?;
+        if let Some(previous) = previous {
+            return Err(format!(
+                "{} was already applied on {} (as {})",
+                file_name,
+                previous.applied_at,
+                previous.name.unwrap_or_default()
+            ));
+        }
+
+        execute_schema_patch(&mut conn, &patch_sql, true, |conn, executed| {
+            record.statement_count = executed as i64;
+            conn.pragma_update(None, "user_version", version)
+                .and_then(|_| crate::migrations::record(conn, &record))
+                .map_err(|e| format!("Failed to record migration: {}", e))
+        })
+        .await
+        .map_err(|e| {
+            format!(
+                "Migration {} failed ({} applied before it): {}",
+                file_name,
+                result.applied.len(),
+                e
+            )
+        })?;
+
+        println!("🧱 Applied migration {} to {}", file_name, target_db_path);
+        result.user_version = version;
+        result.applied.push(record);
+    }
+
+    drop(conn);
+    if !result.applied.is_empty() {
+        if let Err(e) = db_manager.reconnect(&target_db_path) {
+            println!("⚠️ Failed to reconnect {}: {}", target_db_path, e);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Patches recorded in a connected database's `plandb_migrations` table, oldest first
+#[tauri::command]
+pub async fn get_migration_history(
+    db_path: String,
+    manager: State<'_, DbManager>,
+) -> Result<Vec<MigrationRecord>, String> {
+    let conn = manager.reader(&db_path).map_err(|e| e.to_string())?;
+    crate::migrations::history(&conn)
+        .map_err(|e| format!("Failed to read migration history: {}", e))
+}
+
+// Execute a schema patch, committing every BATCH_SIZE statements. BEGIN/COMMIT in the
+// patch are skipped because transactions are managed here; `before_commit` runs inside
+// the final transaction with the number of statements executed. Tracked patches pass
+// `single_transaction` so a failure can't leave them half applied without a history row.
+async fn execute_schema_patch(
+    conn: &mut Connection,
+    patch_sql: &str,
+    single_transaction: bool,
+    before_commit: impl FnOnce(&Connection, usize) -> Result<(), String>,
+) -> Result<usize, String> {
+    const BATCH_SIZE: usize = 500; // Commit every 500 statements to prevent long locks
+                                   // Prepare statements - filter and clean first
     let all_statements: Vec<&str> = patch_sql.split(';').collect();
     let mut cleaned_statements: Vec<String> = Vec::new();
 
@@ -741,7 +1555,7 @@ pub async fn apply_schema_patch(
 
         // Commit and start new transaction every BATCH_SIZE statements
         // This prevents long-running transactions that lock the database
-        if executed % BATCH_SIZE == 0 && idx < cleaned_statements.len() - 1 {
+        if !single_transaction && executed % BATCH_SIZE == 0 && idx < cleaned_statements.len() - 1 {
             conn.execute("COMMIT", [])
                 .map_err(|e| format!("Failed to commit batch: {}", e))?;
 
@@ -753,25 +1567,40 @@ pub async fn apply_schema_patch(
         }
     }
 
+    if let Err(e) = before_commit(conn, executed) {
+        let _ = conn.execute("ROLLBACK", []);
+        return Err(e);
+    }
+
     // Final commit
     conn.execute("COMMIT", [])
         .map_err(|e| format!("Failed to final commit: {}", e))?;
 
-    // Close the connection to flush changes
-    drop(conn);
+    Ok(executed)
+}
 
-    // Force DatabaseManager to reconnect to see the updated schema
-    {
-        let mut manager = db_manager.lock().unwrap();
-        // Disconnect and reconnect to refresh the schema cache
-        manager.disconnect(&target_db_path);
-        let _ = manager.connect_database(&target_db_path, &db_password, None);
+// Open the target of an explicit write. Browsing and comparison connections are
+// read-only, so applying a patch is the only place a database gets a writable one.
+fn open_for_apply(db_manager: &DatabaseManager, db_path: &str) -> Result<Connection, String> {
+    if db_manager.access_mode(db_path).is_none() {
+        // Not connected in the app - open as plain SQLite like before
+        let conn = Connection::open(db_path)
+            .map_err(|e| format!("Failed to open target database: {}", e))?;
+        unlock_database(&conn, "")?;
+        return Ok(conn);
     }
 
-    Ok(format!(
-        "Schema patch applied successfully. Executed {} statements.",
-        executed
-    ))
+    let conn = crate::editing::open_for_edit(db_manager, db_path)
+        .map_err(|e| format!("Failed to open target database: {}", e))?;
+
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table';",
+        [],
+        |_| Ok(()),
+    )
+    .map_err(|e| format!("Failed to unlock target database: {}", e))?;
+
+    Ok(conn)
 }
 
 // Helper function to unlock database
@@ -870,14 +1699,8 @@ fn generate_table_recreation_sql(
     // Step 4: Create new table with source schema (make target match source)
     sql.push_str(&format!("{};\n\n", source_schema));
 
-    // Step 5: Copy data from old to new table
-    sql.push_str(&format!(
-        "INSERT INTO `{}` ({})\nSELECT {}\nFROM `{}_old`;\n\n",
-        table_name, common_cols_str, common_cols_str, table_name
-    ));
-
-    // Step 6: Drop old table
-    sql.push_str(&format!("DROP TABLE `{}_old`;\n\n", table_name));
+    // Steps 5-6: Copy data from old to new table, then drop the old one
+    copy_into_recreated_table(&mut sql, table_name, &source_schema, &common_cols_str);
 
     // Step 7: Commit this table's transaction
     sql.push_str("COMMIT;\n\n");
@@ -903,11 +1726,15 @@ fn generate_table_recreation_sql(
     if !diff.modified_columns.is_empty() {
         for mod_col in &diff.modified_columns {
             sql.push_str(&format!(
-                "--   Modified: {} ({} -> {})\n",
-                mod_col.column_name, mod_col.old_type, mod_col.new_type
+                "--   Modified: {} ({})\n",
+                mod_col.column_name,
+                mod_col.changes.join(", ")
             ));
         }
     }
+    if diff.column_order_changed {
+        sql.push_str("--   Column order changed to match source\n");
+    }
 
     Ok(sql)
 }
@@ -970,14 +1797,8 @@ fn generate_table_recreation_sql_reverse(
     // Step 4: Create new table with target schema (make source match target)
     sql.push_str(&format!("{};\n\n", target_schema));
 
-    // Step 5: Copy data from old to new table
-    sql.push_str(&format!(
-        "INSERT INTO `{}` ({})\nSELECT {}\nFROM `{}_old`;\n\n",
-        table_name, common_cols_str, common_cols_str, table_name
-    ));
-
-    // Step 6: Drop old table
-    sql.push_str(&format!("DROP TABLE `{}_old`;\n\n", table_name));
+    // Steps 5-6: Copy data from old to new table, then drop the old one
+    copy_into_recreated_table(&mut sql, table_name, &target_schema, &common_cols_str);
 
     // Step 7: Commit transaction
     sql.push_str("COMMIT;\n\n");
@@ -991,34 +1812,184 @@ fn generate_table_recreation_sql_reverse(
         let added: Vec<String> = diff.added_columns.iter().map(|c| c.name.clone()).collect();
         sql.push_str(&format!("--   Added (from target): {}\n", added.join(", ")));
     }
-    if !diff.removed_columns.is_empty() {
-        sql.push_str(&format!(
-            "--   Dropped (not in target): {}\n",
-            diff.removed_columns.join(", ")
-        ));
+    if !diff.removed_columns.is_empty() {
+        sql.push_str(&format!(
+            "--   Dropped (not in target): {}\n",
+            diff.removed_columns.join(", ")
+        ));
+    }
+    if !diff.modified_columns.is_empty() {
+        for mod_col in &diff.modified_columns {
+            sql.push_str(&format!(
+                "--   Modified: {} ({} -> {})\n",
+                mod_col.column_name, mod_col.new_type, mod_col.old_type
+            ));
+            if mod_col.old_default != mod_col.new_default {
+                sql.push_str(&format!(
+                    "--     default: {} -> {}\n",
+                    mod_col.new_default.as_deref().unwrap_or("none"),
+                    mod_col.old_default.as_deref().unwrap_or("none")
+                ));
+            }
+        }
+    }
+    if diff.column_order_changed {
+        sql.push_str("--   Column order changed to match target\n");
+    }
+
+    Ok(sql)
+}
+
+// Copy the rows of `{table}_old` into the recreated table and drop the old table. The
+// AUTOINCREMENT counter is carried over, so ids of deleted rows aren't handed out again.
+fn copy_into_recreated_table(
+    sql: &mut String,
+    table_name: &str,
+    new_schema: &str,
+    common_cols_str: &str,
+) {
+    let (options, virtual_module) = crate::database::table_options(new_schema);
+    let content = fts_content_table(new_schema);
+
+    match content.as_deref() {
+        // External-content FTS tables index another table; they are rebuilt from it
+        Some(content) if !content.is_empty() => sql.push_str(&format!(
+            "-- `{}` indexes `{}`; its index is rebuilt below\n\n",
+            table_name, content
+        )),
+        Some(_) => sql.push_str(&format!(
+            "-- NOTE: `{}` is a contentless FTS table; its rows can't be copied and must be indexed again\n\n",
+            table_name
+        )),
+        None => {
+            if options.iter().any(|o| o == "STRICT") {
+                sql.push_str(
+                    "-- NOTE: STRICT table - values that don't match their column's type will fail the copy\n",
+                );
+            }
+            sql.push_str(&format!(
+                "INSERT INTO `{}` ({})\nSELECT {}\nFROM `{}_old`;\n\n",
+                table_name, common_cols_str, common_cols_str, table_name
+            ));
+        }
+    }
+
+    // Renaming moved the counter to `{table}_old`, and the copy started a new one
+    if virtual_module.is_none() && options.iter().any(|o| o == "AUTOINCREMENT") {
+        sql.push_str(&format!(
+            "DELETE FROM sqlite_sequence WHERE name = '{}';\n",
+            table_name.replace('\'', "''")
+        ));
+        sql.push_str(&format!(
+            "INSERT INTO sqlite_sequence (name, seq)\nSELECT '{}', seq FROM sqlite_sequence WHERE name = '{}_old';\n\n",
+            table_name.replace('\'', "''"),
+            table_name.replace('\'', "''")
+        ));
+    }
+
+    sql.push_str(&format!("DROP TABLE `{}_old`;\n\n", table_name));
+}
+
+// Changes ALTER TABLE can't make: table options, generated columns (which must keep their
+// expression) and anything on a virtual table. These always recreate the table.
+fn needs_table_rebuild(
+    conn: &Connection,
+    table_name: &str,
+    diff: &crate::models::TableDiff,
+    columns_to_add: &[ColumnInfo],
+) -> bool {
+    !diff.option_changes.is_empty()
+        || columns_to_add.iter().any(|c| c.generated.is_some())
+        || get_create_table_sql(conn, table_name)
+            .is_ok_and(|sql| crate::database::table_options(&sql).1.is_some())
+}
+
+// The `content=` table of an FTS4/FTS5 table: `Some("")` for a contentless one, `None`
+// for other tables and FTS tables that store their own content
+fn fts_content_table(create_sql: &str) -> Option<String> {
+    let (_, module) = crate::database::table_options(create_sql);
+    if !matches!(module.as_deref(), Some("fts4" | "fts5")) {
+        return None;
+    }
+    let start = create_sql.find('(')?;
+    let end = create_sql.rfind(')')?;
+    let args = create_sql.get(start + 1..end)?;
+
+    // Split the module arguments on commas outside quotes
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    for c in args.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, ',') => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
     }
-    if !diff.modified_columns.is_empty() {
-        for mod_col in &diff.modified_columns {
+    parts.push(current);
+
+    parts.iter().find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case("content") {
+            return None;
+        }
+        let value = value.trim();
+        let unquoted = value
+            .strip_prefix(['\'', '"', '`', '['])
+            .and_then(|v| v.strip_suffix(['\'', '"', '`', ']']))
+            .unwrap_or(value);
+        Some(unquoted.to_string())
+    })
+}
+
+// Rebuild the external-content FTS indexes of `template_conn`'s schema that a patch
+// created, or whose content table it created or recreated (which renumbers rowids)
+fn fts_rebuild_sql(template_conn: &Connection, touched: &[String]) -> Result<String, String> {
+    let mut stmt = template_conn
+        .prepare("SELECT name, sql FROM sqlite_master WHERE type = 'table' AND sql LIKE 'CREATE VIRTUAL TABLE%'")
+        .map_err(|e| format!("Failed to list virtual tables: {}", e))?;
+    let tables = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("Failed to list virtual tables: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to list virtual tables: {}", e))?;
+
+    let is_touched = |name: &str| touched.iter().any(|t| t.eq_ignore_ascii_case(name));
+    let mut sql = String::new();
+    for (name, create_sql) in tables {
+        let Some(content) = fts_content_table(&create_sql).filter(|c| !c.is_empty()) else {
+            continue;
+        };
+        if is_touched(&name) || is_touched(&content) {
             sql.push_str(&format!(
-                "--   Modified: {} ({} -> {})\n",
-                mod_col.column_name, mod_col.new_type, mod_col.old_type
+                "INSERT INTO `{}` (`{}`) VALUES ('rebuild');\n",
+                name, name
             ));
         }
     }
-
+    if !sql.is_empty() {
+        sql.insert_str(0, "-- Rebuild full-text indexes of recreated tables\n");
+        sql.push('\n');
+    }
     Ok(sql)
 }
 
-// Get column names from a table
+// Get the names of the columns rows can be copied into (no generated or hidden columns)
 fn get_table_column_names(conn: &Connection, table_name: &str) -> Result<Vec<String>, String> {
     let mut stmt = conn
-        .prepare(&format!("PRAGMA table_info(`{}`)", table_name))
+        .prepare("SELECT name FROM pragma_table_xinfo(?1) WHERE hidden = 0 ORDER BY cid")
         .map_err(|e| format!("Failed to get table info: {}", e))?;
 
     let columns: Vec<String> = stmt
-        .query_map([], |row| {
-            row.get::<_, String>(1) // Column name is at index 1
-        })
+        .query_map([table_name], |row| row.get::<_, String>(0))
         .map_err(|e| format!("Failed to query columns: {}", e))?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| format!("Failed to collect columns: {}", e))?;
@@ -1040,8 +2011,9 @@ fn get_column_info(
     table_name: &str,
     column_name: &str,
 ) -> Result<ColumnInfo, String> {
+    // table_xinfo also lists generated columns (hidden 2 or 3)
     let mut stmt = conn
-        .prepare(&format!("PRAGMA table_info(`{}`)", table_name))
+        .prepare(&format!("PRAGMA table_xinfo(`{}`)", table_name))
         .map_err(|e| format!("Failed to get table info: {}", e))?;
 
     let column = stmt
@@ -1051,7 +2023,12 @@ fn get_column_info(
                 data_type: row.get(2)?,
                 is_nullable: row.get::<_, i32>(3)? == 0,
                 default_value: row.get(4).ok(),
-                is_primary_key: row.get::<_, i32>(5)? == 1,
+                is_primary_key: row.get::<_, i32>(5)? > 0,
+                generated: match row.get::<_, i32>(6)? {
+                    2 => Some("VIRTUAL".to_string()),
+                    3 => Some("STORED".to_string()),
+                    _ => None,
+                },
             })
         })
         .map_err(|e| format!("Failed to query columns: {}", e))?
@@ -1132,27 +2109,46 @@ pub async fn generate_table_schema_patch(
     table_name: String,
     table_status: String,      // "added", "removed", or "modified"
     direction: Option<String>, // "source_to_target" (default) or "target_to_source"
-    db_manager: State<'_, Mutex<DatabaseManager>>,
+    filter: Option<ComparisonFilter>,
+    rules: Option<ComparisonRules>,
+    db_manager: State<'_, DbManager>,
 ) -> Result<String, String> {
-    let manager = db_manager.lock().unwrap();
+    let manager = db_manager.inner();
+    let filter = filter.unwrap_or_default();
+    let rules = rules.unwrap_or_default();
+    if !filter.includes_table(&table_name) {
+        return Err(format!(
+            "Table '{}' is excluded by the comparison filter",
+            table_name
+        ));
+    }
 
     // Determine direction
     let is_reverse = direction.as_deref() == Some("target_to_source");
 
-    // Open BOTH databases
-    let source_conn = Connection::open(&db1_path)
+    // The schema the patch expects to find, checked before it is applied
+    let (template, apply_to) = if is_reverse {
+        (&db2_path, &db1_path)
+    } else {
+        (&db1_path, &db2_path)
+    };
+    // Verified like a patch of the whole schema limited to this table
+    let table_filter = ComparisonFilter {
+        include_tables: vec![table_name.clone()],
+        ..filter.clone()
+    };
+    let header = integrity::new_header(manager, template, apply_to, &table_filter, &rules)
+        .map_err(|e| e.to_string())?;
+
+    // Open BOTH databases (read-only, already unlocked)
+    let source_conn = manager
+        .reader(&db1_path)
         .map_err(|e| format!("Failed to open source database: {}", e))?;
 
-    let target_conn = Connection::open(&db2_path)
+    let target_conn = manager
+        .reader(&db2_path)
         .map_err(|e| format!("Failed to open target database: {}", e))?;
 
-    // Get passwords and unlock if needed
-    let db1_password = manager.get_password(&db1_path).unwrap_or_default();
-    let db2_password = manager.get_password(&db2_path).unwrap_or_default();
-
-    unlock_database(&source_conn, &db1_password)?;
-    unlock_database(&target_conn, &db2_password)?;
-
     // Generate patch header
     let mut sql = String::new();
     sql.push_str(&format!("-- Single Table Schema Patch: {}\n", table_name));
@@ -1208,7 +2204,7 @@ pub async fn generate_table_schema_patch(
         "modified" => {
             // Modified table: needs comparison to determine changes
             let comparison = manager
-                .compare_schemas(&db1_path, &db2_path)
+                .compare_schemas(&db1_path, &db2_path, &filter, &rules)
                 .map_err(|e| e.to_string())?;
 
             // Find the specific table diff
@@ -1221,7 +2217,14 @@ pub async fn generate_table_schema_patch(
             if is_reverse {
                 // Reverse: Make SOURCE match TARGET
                 let needs_recreation = !table_diff.removed_columns.is_empty()
-                    || !table_diff.modified_columns.is_empty();
+                    || !table_diff.modified_columns.is_empty()
+                    || table_diff.column_order_changed
+                    || needs_table_rebuild(
+                        &source_conn,
+                        &table_name,
+                        table_diff,
+                        &table_diff.added_columns,
+                    );
 
                 if needs_recreation {
                     sql.push_str("-- Recreate table to match target schema\n");
@@ -1240,12 +2243,13 @@ pub async fn generate_table_schema_patch(
                     }
                 } else if !table_diff.added_columns.is_empty() {
                     // Check if columns need middle insertion (smart detection)
-                    let needs_recreation = needs_column_order_recreation(
-                        &source_conn,
-                        &target_conn,
-                        &table_name,
-                        &table_diff.added_columns,
-                    )?;
+                    let needs_recreation = !rules.ignore_column_order
+                        && needs_column_order_recreation(
+                            &source_conn,
+                            &target_conn,
+                            &table_name,
+                            &table_diff.added_columns,
+                        )?;
 
                     if needs_recreation {
                         // Use table recreation to preserve column order
@@ -1297,7 +2301,8 @@ pub async fn generate_table_schema_patch(
                 // 2. Need to drop columns (added_columns)
                 // 3. Need to add columns in middle (removed_columns with position check)
 
-                let has_modifications = !table_diff.modified_columns.is_empty();
+                let has_modifications =
+                    !table_diff.modified_columns.is_empty() || table_diff.column_order_changed;
                 let needs_drop_columns = !table_diff.added_columns.is_empty();
 
                 // Check if removed_columns (adding to target) need middle insertion
@@ -1309,19 +2314,22 @@ pub async fn generate_table_schema_patch(
                     })
                     .collect();
 
-                let needs_order_preservation = if !columns_to_add.is_empty() {
-                    needs_column_order_recreation(
-                        &target_conn,
-                        &source_conn,
-                        &table_name,
-                        &columns_to_add,
-                    )?
-                } else {
-                    false
-                };
+                let needs_order_preservation =
+                    if !columns_to_add.is_empty() && !rules.ignore_column_order {
+                        needs_column_order_recreation(
+                            &target_conn,
+                            &source_conn,
+                            &table_name,
+                            &columns_to_add,
+                        )?
+                    } else {
+                        false
+                    };
 
-                let needs_recreation =
-                    has_modifications || needs_drop_columns || needs_order_preservation;
+                let needs_recreation = has_modifications
+                    || needs_drop_columns
+                    || needs_order_preservation
+                    || needs_table_rebuild(&target_conn, &table_name, table_diff, &columns_to_add);
 
                 if needs_recreation {
                     if needs_drop_columns && !needs_order_preservation && !has_modifications {
@@ -1373,9 +2381,18 @@ pub async fn generate_table_schema_patch(
         }
     }
 
+    // A created or recreated table renumbers rowids that full-text indexes point at
+    let template_conn = if is_reverse {
+        &target_conn
+    } else {
+        &source_conn
+    };
+    let touched_tables = [table_name.clone()];
+    sql.push_str(&fts_rebuild_sql(template_conn, &touched_tables)?);
+
     sql.push_str("\n-- Table patch complete\n");
 
-    Ok(sql)
+    Ok(integrity::sign(header, &sql))
 }
 
 #[tauri::command]
@@ -1385,7 +2402,9 @@ pub async fn generate_data_patch(
     table_comparisons: Vec<serde_json::Value>,
     direction: Option<String>,
     patch_type: Option<String>,
-    db_manager: State<'_, Mutex<DatabaseManager>>,
+    filter: Option<ComparisonFilter>,
+    rules: Option<ComparisonRules>,
+    db_manager: State<'_, DbManager>,
 ) -> Result<String, String> {
     // Redirect to file-based generation for consistency and performance
     generate_data_patch_file(
@@ -1394,35 +2413,92 @@ pub async fn generate_data_patch(
         table_comparisons,
         direction,
         patch_type,
+        filter,
+        rules,
         db_manager,
     )
     .await
 }
 
+/// Same as `generate_data_patch_file`, but also writes a rollback patch next to it
+/// (`rollbackFilePath` in the result) that restores the rows as they are now
 #[tauri::command]
-pub async fn generate_data_patch_file(
+pub async fn generate_reversible_data_patch(
     db1_path: String,
     db2_path: String,
     table_comparisons: Vec<serde_json::Value>,
     direction: Option<String>,
     patch_type: Option<String>,
-    db_manager: State<'_, Mutex<DatabaseManager>>,
+    filter: Option<ComparisonFilter>,
+    rules: Option<ComparisonRules>,
+    db_manager: State<'_, DbManager>,
 ) -> Result<String, String> {
-    let db1_password = {
-        let manager = db_manager.lock().unwrap();
-        manager.get_password(&db1_path).unwrap_or_default()
-    };
+    let manager = db_manager.inner().clone();
+    let db1_password = manager.get_password(&db1_path).unwrap_or_default();
+    let db2_password = manager.get_password(&db2_path).unwrap_or_default();
 
-    let db2_password = {
-        let manager = db_manager.lock().unwrap();
-        manager.get_password(&db2_path).unwrap_or_default()
-    };
+    let direction = direction.unwrap_or_else(|| "source_to_target".to_string());
+    let patch_type = patch_type.unwrap_or_else(|| "all".to_string());
+    let filter = filter.unwrap_or_default();
+    let rules = rules.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || {
+        let (template, apply_to) = if direction == "target_to_source" {
+            (&db2_path, &db1_path)
+        } else {
+            (&db1_path, &db2_path)
+        };
+        let header = integrity::new_header(&manager, template, apply_to, &filter, &rules)
+            .map_err(|e| e.to_string())?;
+        let conn = manager
+            .reader(apply_to)
+            .map_err(|e| format!("Failed to open {}: {}", apply_to, e))?;
+        generate_data_patch_blocking_to_file(
+            &db1_path,
+            &db2_path,
+            &table_comparisons,
+            &db1_password,
+            &db2_password,
+            &direction,
+            &patch_type,
+            &filter,
+            &rules,
+            header,
+            Some(&conn),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+pub async fn generate_data_patch_file(
+    db1_path: String,
+    db2_path: String,
+    table_comparisons: Vec<serde_json::Value>,
+    direction: Option<String>,
+    patch_type: Option<String>,
+    filter: Option<ComparisonFilter>,
+    rules: Option<ComparisonRules>,
+    db_manager: State<'_, DbManager>,
+) -> Result<String, String> {
+    let manager = db_manager.inner().clone();
+    let db1_password = manager.get_password(&db1_path).unwrap_or_default();
+    let db2_password = manager.get_password(&db2_path).unwrap_or_default();
 
     let direction = direction.unwrap_or_else(|| "source_to_target".to_string());
     let patch_type = patch_type.unwrap_or_else(|| "all".to_string());
+    let filter = filter.unwrap_or_default();
+    let rules = rules.unwrap_or_default();
 
     // Run blocking database operations in a separate thread pool
     tokio::task::spawn_blocking(move || {
+        let header = if direction == "target_to_source" {
+            integrity::new_header(&manager, &db2_path, &db1_path, &filter, &rules)
+        } else {
+            integrity::new_header(&manager, &db1_path, &db2_path, &filter, &rules)
+        }
+        .map_err(|e| e.to_string())?;
         generate_data_patch_blocking_to_file(
             &db1_path,
             &db2_path,
@@ -1431,12 +2507,19 @@ pub async fn generate_data_patch_file(
             &db2_password,
             &direction,
             &patch_type,
+            &filter,
+            &rules,
+            header,
+            None,
         )
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+// `header` holds the schema fingerprints; the body hash is added once it is written.
+// With `rollback_conn` (a reader on the database the patch applies to) a rollback patch
+// is written alongside, restoring every row the patch touches to its current values
 fn generate_data_patch_blocking_to_file(
     db1_path: &str,
     db2_path: &str,
@@ -1445,6 +2528,10 @@ fn generate_data_patch_blocking_to_file(
     db2_password: &str,
     direction: &str,
     patch_type: &str,
+    filter: &ComparisonFilter,
+    rules: &ComparisonRules,
+    header: PatchHeader,
+    rollback_conn: Option<&Connection>,
 ) -> Result<String, String> {
     use std::fs::File;
     use std::io::Write;
@@ -1455,8 +2542,8 @@ fn generate_data_patch_blocking_to_file(
     let file_name = format!("plandb_patch_{}.sql", timestamp);
     let file_path = temp_dir.join(&file_name);
 
-    let mut file =
-        File::create(&file_path).map_err(|e| format!("Failed to create temp file: {}", e))?;
+    let mut file = SignedPatchWriter::create(&file_path)
+        .map_err(|e| format!("Failed to create temp file: {}", e))?;
 
     let is_reverse = direction == "target_to_source";
 
@@ -1474,10 +2561,27 @@ fn generate_data_patch_blocking_to_file(
     writeln!(file, "").map_err(|e| e.to_string())?;
     writeln!(file, "BEGIN TRANSACTION;\n").map_err(|e| e.to_string())?;
 
+    let rollback_path = temp_dir.join(format!("plandb_patch_{}_rollback.sql", timestamp));
+    let mut rollback = match rollback_conn {
+        Some(conn) => {
+            let rollback_file = SignedPatchWriter::create(&rollback_path)
+                .map_err(|e| format!("Failed to create rollback file: {}", e))?;
+            let apply_to = if is_reverse { db1_path } else { db2_path };
+            Some(
+                RollbackWriter::new(conn, rollback_file, &file_name, apply_to)
+                    .map_err(|e| e.to_string())?,
+            )
+        }
+        None => None,
+    };
+
     // Process tables and write directly to file
     for comparison in table_comparisons {
         let table_name = comparison["tableName"].as_str().unwrap_or("unknown");
         let key_column = comparison["keyColumn"].as_str().unwrap_or("id");
+        if !filter.includes_table(table_name) {
+            continue;
+        }
 
         // Get column info
         // The frontend sends the comparison data nested inside a "comparison" object
@@ -1530,6 +2634,11 @@ fn generate_data_patch_blocking_to_file(
                                 table_name, key_column, key_value
                             )
                             .map_err(|e| e.to_string())?;
+                            if let Some(rollback) = rollback.as_mut() {
+                                rollback
+                                    .undo_delete(table_name, key_column, &row[key_column])
+                                    .map_err(|e| e.to_string())?;
+                            }
                         }
                     } else {
                         // Forward: INSERT into target
@@ -1543,6 +2652,11 @@ fn generate_data_patch_blocking_to_file(
                         for row in missing {
                             let insert_sql = generate_insert_statement(table_name, &columns, row)?;
                             writeln!(file, "{}", insert_sql).map_err(|e| e.to_string())?;
+                            if let Some(rollback) = rollback.as_mut() {
+                                rollback
+                                    .undo_insert(table_name, key_column, &row[key_column])
+                                    .map_err(|e| e.to_string())?;
+                            }
                         }
                     }
                     writeln!(file, "").map_err(|e| e.to_string())?;
@@ -1570,6 +2684,11 @@ fn generate_data_patch_blocking_to_file(
                         for row in extra {
                             let insert_sql = generate_insert_statement(table_name, &columns, row)?;
                             writeln!(file, "{}", insert_sql).map_err(|e| e.to_string())?;
+                            if let Some(rollback) = rollback.as_mut() {
+                                rollback
+                                    .undo_insert(table_name, key_column, &row[key_column])
+                                    .map_err(|e| e.to_string())?;
+                            }
                         }
                     } else {
                         // Forward: DELETE from target
@@ -1595,6 +2714,11 @@ fn generate_data_patch_blocking_to_file(
                                 table_name, key_column, key_value
                             )
                             .map_err(|e| e.to_string())?;
+                            if let Some(rollback) = rollback.as_mut() {
+                                rollback
+                                    .undo_delete(table_name, key_column, &row[key_column])
+                                    .map_err(|e| e.to_string())?;
+                            }
                         }
                     }
                     writeln!(file, "").map_err(|e| e.to_string())?;
@@ -1609,15 +2733,38 @@ fn generate_data_patch_blocking_to_file(
                 .get("differentRows")
                 .and_then(|v| v.as_array())
             {
-                if !different.is_empty() {
+                // Ignored columns, and values equal under the rules, never make a
+                // row different
+                let updates: Vec<(&serde_json::Value, Vec<&str>)> = different
+                    .iter()
+                    .map(|diff| {
+                        let columns: Vec<&str> = diff
+                            .get("differentColumns")
+                            .and_then(|v| v.as_array())
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|col| col.as_str())
+                            .filter(|col| {
+                                !filter.ignores_column(table_name, col)
+                                    && !rules.json_values_equal(
+                                        &diff["sourceRow"][*col],
+                                        &diff["targetRow"][*col],
+                                    )
+                            })
+                            .collect();
+                        (diff, columns)
+                    })
+                    .filter(|(_, columns)| !columns.is_empty())
+                    .collect();
+                if !updates.is_empty() {
                     writeln!(
                         file,
                         "-- UPDATE {} different rows in {}",
-                        different.len(),
+                        updates.len(),
                         table_name
                     )
                     .map_err(|e| e.to_string())?;
-                    for diff in different {
+                    for (diff, diff_cols) in updates {
                         let row_data = if is_reverse {
                             &diff["targetRow"] // Use target data to update source
                         } else {
@@ -1629,28 +2776,27 @@ fn generate_data_patch_blocking_to_file(
                         let key_value_formatted = format_value_for_sql(key_value_json);
 
                         let mut set_clauses = Vec::new();
-                        if let Some(diff_cols) =
-                            diff.get("differentColumns").and_then(|v| v.as_array())
-                        {
-                            for col_val in diff_cols {
-                                if let Some(col_name) = col_val.as_str() {
-                                    let val = &row_data[col_name];
-                                    let formatted_val = format_value_for_sql(val);
-                                    set_clauses.push(format!("{} = {}", col_name, formatted_val));
-                                }
-                            }
+                        let mut set_columns = Vec::new();
+                        for col_name in diff_cols {
+                            let val = &row_data[col_name];
+                            let formatted_val = format_value_for_sql(val);
+                            set_clauses.push(format!("{} = {}", col_name, formatted_val));
+                            set_columns.push(col_name.to_string());
                         }
 
-                        if !set_clauses.is_empty() {
-                            writeln!(
-                                file,
-                                "UPDATE {} SET {} WHERE {} = {};",
-                                table_name,
-                                set_clauses.join(", "),
-                                key_column,
-                                key_value_formatted
-                            )
-                            .map_err(|e| e.to_string())?;
+                        writeln!(
+                            file,
+                            "UPDATE {} SET {} WHERE {} = {};",
+                            table_name,
+                            set_clauses.join(", "),
+                            key_column,
+                            key_value_formatted
+                        )
+                        .map_err(|e| e.to_string())?;
+                        if let Some(rollback) = rollback.as_mut() {
+                            rollback
+                                .undo_update(table_name, key_column, key_value_json, &set_columns)
+                                .map_err(|e| e.to_string())?;
                         }
                     }
                     writeln!(file, "").map_err(|e| e.to_string())?;
@@ -1661,14 +2807,16 @@ fn generate_data_patch_blocking_to_file(
 
     writeln!(file, "COMMIT;").map_err(|e| e.to_string())?;
 
-    // IMPORTANT: Flush the file buffer to ensure all data is written to disk
-    // before reading metadata. Otherwise, metadata.len() will only reflect
-    // the partial write that happened to reach the OS.
-    file.flush()
-        .map_err(|e| format!("Failed to flush file: {}", e))?;
-
-    // Drop the file handle to ensure it's fully closed
-    drop(file);
+    // Puts the header in front and closes the file, so it is fully on disk before
+    // reading metadata. Otherwise, metadata.len() would only reflect the partial
+    // write that happened to reach the OS.
+    let rollback_header = PatchHeader {
+        source_schema: header.target_schema.clone(),
+        template: None,
+        ..header.clone()
+    };
+    file.finish(header)
+        .map_err(|e| format!("Failed to write patch file: {}", e))?;
 
     // Get file size from disk (now that it's fully written)
     let metadata = std::fs::metadata(&file_path).map_err(|e| e.to_string())?;
@@ -1687,12 +2835,22 @@ fn generate_data_patch_blocking_to_file(
     }
 
     // Return JSON with file info
-    let result = serde_json::json!({
+    let mut result = serde_json::json!({
         "filePath": file_path.to_string_lossy(),
         "fileSize": file_size,
         "preview": final_preview,
         "isLarge": file_size > 5 * 1024 * 1024 // Flag as large if > 5MB
     });
+    if let Some(rollback) = rollback {
+        // A data patch leaves the schema alone, so the rollback expects the same one
+        let statements = rollback.statements;
+        rollback
+            .finish()
+            .and_then(|out| out.finish(rollback_header))
+            .map_err(|e| format!("Failed to write rollback file: {}", e))?;
+        result["rollbackFilePath"] = rollback_path.to_string_lossy().into();
+        result["rollbackStatements"] = statements.into();
+    }
 
     Ok(result.to_string())
 }
@@ -1701,21 +2859,18 @@ fn generate_data_patch_blocking_to_file(
 pub async fn apply_data_patch(
     target_db_path: String,
     patch_sql: String,
-    db_manager: State<'_, Mutex<DatabaseManager>>,
+    force: Option<bool>, // Apply even if the target's schema has changed since generation
+    db_manager: State<'_, DbManager>,
 ) -> Result<String, String> {
     const BATCH_SIZE: usize = 1000; // Commit every 1000 statements for data patches
 
-    let db_password = {
-        let manager = db_manager.lock().unwrap();
-        manager.get_password(&target_db_path).unwrap_or_default()
-    };
-
-    // Open target database
-    let conn = Connection::open(&target_db_path)
-        .map_err(|e| format!("Failed to open target database: {}", e))?;
+    // Open target database (writable - this is an explicit apply)
+    let conn = open_for_apply(&db_manager, &target_db_path)?;
 
-    // Unlock if needed
-    unlock_database(&conn, &db_password)?;
+    // Rows are addressed by column; a changed schema would make them land wrongly
+    let drift = integrity::check_schema(&db_manager, &patch_sql, &conn)
+        .map_err(|e| format!("Failed to check target schema: {}", e))?;
+    let drift = accept_mismatch(drift, force)?;
 
     // Prepare statements - filter and clean first
     let all_statements: Vec<&str> = patch_sql.split(';').collect();
@@ -1815,10 +2970,11 @@ pub async fn apply_data_patch(
             .map_err(|e| format!("Failed to final commit: {}", e))?;
     }
 
-    Ok(format!(
+    let message = format!(
         "Data patch applied successfully. Executed {} statements.",
         executed
-    ))
+    );
+    Ok(applied_despite(message, drift))
 }
 
 // Helper to format values for SQL
@@ -2083,95 +3239,19 @@ pub async fn migrate_to_sqlcipher(
         .ok_or("Invalid output path")?
         .to_string();
 
-    // Open source database (unencrypted SQLite)
-    let source_conn = Connection::open(&source_path)
-        .map_err(|e| format!("Failed to open source database: {}", e))?;
-
-    // Get list of tables - FIXED
-    let tables: Vec<String> = {
-        let mut stmt = source_conn
-            .prepare(
-                "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
-            )
-            .map_err(|e| format!("Failed to query tables: {}", e))?;
-
-        let rows = stmt
-            .query_map([], |row| row.get(0))
-            .map_err(|e| format!("Failed to read tables: {}", e))?;
-
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row.map_err(|e| format!("Failed to collect tables: {}", e))?);
-        }
-        result
-    };
-
-    // Get table schemas
-    let mut table_schemas = Vec::new();
-    for table_name in &tables {
-        let schema: String = {
-            let mut schema_stmt = source_conn
-                .prepare("SELECT sql FROM sqlite_master WHERE type='table' AND name=?1")
-                .map_err(|e| format!("Failed to prepare schema query: {}", e))?;
-
-            schema_stmt
-                .query_row([table_name], |row| row.get(0))
-                .map_err(|e| format!("Failed to get schema for table {}: {}", table_name, e))?
-        };
-
-        table_schemas.push((table_name.clone(), schema));
-    }
-
-    // Get indexes - FIXED
-    let indexes: Vec<String> = {
-        let mut idx_stmt = source_conn
-            .prepare("SELECT sql FROM sqlite_master WHERE type='index' AND sql IS NOT NULL")
-            .map_err(|e| format!("Failed to query indexes: {}", e))?;
-
-        let rows = idx_stmt
-            .query_map([], |row| row.get(0))
-            .map_err(|e| format!("Failed to read indexes: {}", e))?;
-
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row.map_err(|e| format!("Failed to collect indexes: {}", e))?);
-        }
-        result
-    };
-
-    // Get triggers - FIXED
-    let triggers: Vec<String> = {
-        let mut trigger_stmt = source_conn
-            .prepare("SELECT sql FROM sqlite_master WHERE type='trigger'")
-            .map_err(|e| format!("Failed to query triggers: {}", e))?;
-
-        let rows = trigger_stmt
-            .query_map([], |row| row.get(0))
-            .map_err(|e| format!("Failed to read triggers: {}", e))?;
+    // Open source database (unencrypted SQLite) - read-only, it is never modified
+    let source_conn =
+        Connection::open_with_flags(&source_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("Failed to open source database: {}", e))?;
 
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row.map_err(|e| format!("Failed to collect triggers: {}", e))?);
-        }
-        result
-    };
-
-    // Get views - FIXED
-    let views: Vec<String> = {
-        let mut view_stmt = source_conn
-            .prepare("SELECT sql FROM sqlite_master WHERE type='view'")
-            .map_err(|e| format!("Failed to query views: {}", e))?;
-
-        let rows = view_stmt
-            .query_map([], |row| row.get(0))
-            .map_err(|e| format!("Failed to read views: {}", e))?;
-
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row.map_err(|e| format!("Failed to collect views: {}", e))?);
-        }
-        result
-    };
+    // Tables, indexes, views and triggers in creation order (shared with the SQL dump)
+    let objects = crate::dump::read_schema_objects(&source_conn)
+        .map_err(|e| format!("Failed to read schema: {}", e))?;
+    let tables: Vec<String> = objects
+        .tables
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect();
 
     // Create encrypted database with SQLCipher
     let mut dest_conn = Connection::open(&output_path)
@@ -2190,7 +3270,7 @@ pub async fn migrate_to_sqlcipher(
         .map_err(|e| format!("Failed to begin transaction: {}", e))?;
 
     // Create tables with fixed collations
-    for (table_name, schema) in &table_schemas {
+    for (table_name, schema) in &objects.tables {
         // Replace unsupported collations with SQLCipher-compatible ones
         // SQLCipher only supports: BINARY, NOCASE, RTRIM
         let fixed_schema = schema
@@ -2258,24 +3338,24 @@ pub async fn migrate_to_sqlcipher(
     }
 
     // Create indexes
-    for index_sql in indexes {
+    for index_sql in &objects.indexes {
         dest_conn
-            .execute(&index_sql, [])
+            .execute(index_sql, [])
             .map_err(|e| format!("Failed to create index: {}", e))?;
     }
 
-    // Create triggers
-    for trigger_sql in triggers {
+    // Create views (before triggers, which may be INSTEAD OF triggers on a view)
+    for view_sql in &objects.views {
         dest_conn
-            .execute(&trigger_sql, [])
-            .map_err(|e| format!("Failed to create trigger: {}", e))?;
+            .execute(view_sql, [])
+            .map_err(|e| format!("Failed to create view: {}", e))?;
     }
 
-    // Create views
-    for view_sql in views {
+    // Create triggers
+    for trigger_sql in &objects.triggers {
         dest_conn
-            .execute(&view_sql, [])
-            .map_err(|e| format!("Failed to create view: {}", e))?;
+            .execute(trigger_sql, [])
+            .map_err(|e| format!("Failed to create trigger: {}", e))?;
     }
 
     // Commit transaction
@@ -2411,3 +3491,261 @@ fn apply_sqlcipher_settings(
 
     Ok(())
 }
+
+// ============================================================================
+// Connection profiles & workspace
+// ============================================================================
+
+#[tauri::command]
+pub async fn list_connection_profiles(
+    profile_manager: State<'_, ProfileManagerState>,
+) -> Result<Vec<ConnectionProfile>, String> {
+    let manager = profile_manager.lock().unwrap();
+    Ok(manager.list_profiles())
+}
+
+#[tauri::command]
+pub async fn save_connection_profile(
+    profile: ConnectionProfile,
+    profile_manager: State<'_, ProfileManagerState>,
+    db_manager: State<'_, DbManager>,
+) -> Result<ConnectionProfile, String> {
+    let saved = {
+        let mut manager = profile_manager.lock().unwrap();
+        manager.save_profile(profile)?
+    };
+
+    // Keep the alias of an already connected database in sync with its profile
+    db_manager.set_alias(&saved.path, saved.alias.clone());
+
+    println!("💾 Saved connection profile {} ({})", saved.id, saved.path);
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn rename_connection_profile(
+    id: String,
+    alias: Option<String>,
+    profile_manager: State<'_, ProfileManagerState>,
+    db_manager: State<'_, DbManager>,
+) -> Result<ConnectionProfile, String> {
+    let renamed = {
+        let mut manager = profile_manager.lock().unwrap();
+        manager.rename_profile(&id, alias)?
+    };
+
+    db_manager.set_alias(&renamed.path, renamed.alias.clone());
+
+    Ok(renamed)
+}
+
+#[tauri::command]
+pub async fn delete_connection_profile(
+    id: String,
+    profile_manager: State<'_, ProfileManagerState>,
+) -> Result<(), String> {
+    let mut manager = profile_manager.lock().unwrap();
+    manager.delete_profile(&id)
+}
+
+#[tauri::command]
+pub async fn set_profile_key_columns(
+    id: String,
+    key_columns: HashMap<String, String>,
+    profile_manager: State<'_, ProfileManagerState>,
+) -> Result<ConnectionProfile, String> {
+    let mut manager = profile_manager.lock().unwrap();
+    manager.set_key_columns(&id, key_columns)
+}
+
+#[tauri::command]
+pub async fn get_workspace(
+    profile_manager: State<'_, ProfileManagerState>,
+) -> Result<WorkspaceState, String> {
+    let manager = profile_manager.lock().unwrap();
+    Ok(manager.workspace())
+}
+
+#[tauri::command]
+pub async fn save_workspace(
+    workspace: WorkspaceState,
+    profile_manager: State<'_, ProfileManagerState>,
+) -> Result<WorkspaceState, String> {
+    let mut manager = profile_manager.lock().unwrap();
+    manager.save_workspace(workspace)
+}
+
+/// Reconnect every database of the saved workspace (or of the given profile ids).
+/// Passwords are never persisted, so encrypted profiles need theirs passed in by profile id.
+#[tauri::command]
+pub async fn open_workspace(
+    profile_ids: Option<Vec<String>>,
+    passwords: Option<HashMap<String, String>>,
+    profile_manager: State<'_, ProfileManagerState>,
+    db_manager: State<'_, DbManager>,
+) -> Result<WorkspaceOpenResult, String> {
+    let (profiles, comparison) = {
+        let manager = profile_manager.lock().unwrap();
+        let workspace = manager.workspace();
+
+        let ids = profile_ids.unwrap_or_else(|| workspace.open_profiles.clone());
+        let mut profiles = Vec::new();
+        for id in &ids {
+            let profile = manager
+                .get_profile(id)
+                .cloned()
+                .ok_or_else(|| format!("Profile '{}' not found", id))?;
+            profiles.push(profile);
+        }
+
+        // Pull in the default compare partner of a single opened profile
+        if profiles.len() == 1 {
+            if let Some(partner) = profiles[0]
+                .compare_partner
+                .as_ref()
+                .and_then(|id| manager.get_profile(id))
+            {
+                profiles.push(partner.clone());
+            }
+        }
+
+        let comparison = workspace.comparison.filter(|(source, target)| {
+            profiles.iter().any(|p| &p.id == source) && profiles.iter().any(|p| &p.id == target)
+        });
+        let comparison = comparison.or_else(|| {
+            if profiles.len() == 2 && profiles[0].compare_partner.as_ref() == Some(&profiles[1].id)
+            {
+                Some((profiles[0].id.clone(), profiles[1].id.clone()))
+            } else {
+                None
+            }
+        });
+
+        (profiles, comparison)
+    };
+
+    let passwords = passwords.unwrap_or_default();
+    let mut results = Vec::new();
+    for profile in profiles {
+        let password = passwords.get(&profile.id).cloned().unwrap_or_default();
+
+        let manager = db_manager.inner().clone();
+        let (path, settings) = (profile.path.clone(), profile.cipher_settings.clone());
+        let access_mode = profile.access_mode;
+        let result = tokio::task::spawn_blocking(move || {
+            manager.connect_database(&path, &password, settings, access_mode)
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+
+        match result {
+            Ok(_) => {
+                let db_info = db_manager.set_alias(&profile.path, profile.alias.clone());
+                println!("✅ Reopened workspace database: {}", profile.path);
+                results.push(WorkspaceProfileResult {
+                    profile,
+                    database: db_info,
+                    error: None,
+                });
+            }
+            Err(e) => {
+                println!("❌ Failed to reopen {}: {}", profile.path, e);
+                results.push(WorkspaceProfileResult {
+                    profile,
+                    database: None,
+                    error: Some(format!("Connection failed: {}", e)),
+                });
+            }
+        }
+    }
+
+    Ok(WorkspaceOpenResult {
+        profiles: results,
+        comparison,
+    })
+}
+
+// ============================================================================
+// Row editing
+// ============================================================================
+
+#[tauri::command]
+pub async fn update_table_row(
+    db_path: String,
+    table_name: String,
+    key: serde_json::Map<String, serde_json::Value>,
+    changes: serde_json::Map<String, serde_json::Value>,
+    expected: Option<serde_json::Map<String, serde_json::Value>>,
+    db_manager: State<'_, DbManager>,
+    edit_log: State<'_, EditLog>,
+) -> Result<RowEdit, String> {
+    let (manager, log) = (db_manager.inner().clone(), edit_log.inner().clone());
+    tokio::task::spawn_blocking(move || {
+        log.update_row(
+            &manager,
+            &db_path,
+            &table_name,
+            &key,
+            &changes,
+            expected.as_ref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Update failed: {}", e))
+}
+
+#[tauri::command]
+pub async fn insert_table_row(
+    db_path: String,
+    table_name: String,
+    values: serde_json::Map<String, serde_json::Value>,
+    db_manager: State<'_, DbManager>,
+    edit_log: State<'_, EditLog>,
+) -> Result<RowEdit, String> {
+    let (manager, log) = (db_manager.inner().clone(), edit_log.inner().clone());
+    tokio::task::spawn_blocking(move || log.insert_row(&manager, &db_path, &table_name, &values))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| format!("Insert failed: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_table_row(
+    db_path: String,
+    table_name: String,
+    key: serde_json::Map<String, serde_json::Value>,
+    expected: Option<serde_json::Map<String, serde_json::Value>>,
+    db_manager: State<'_, DbManager>,
+    edit_log: State<'_, EditLog>,
+) -> Result<RowEdit, String> {
+    let (manager, log) = (db_manager.inner().clone(), edit_log.inner().clone());
+    tokio::task::spawn_blocking(move || {
+        log.delete_row(&manager, &db_path, &table_name, &key, expected.as_ref())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(|e| format!("Delete failed: {}", e))
+}
+
+/// Revert the most recent row edit on a database; returns the edit that was undone
+#[tauri::command]
+pub async fn undo_last_edit(
+    db_path: String,
+    db_manager: State<'_, DbManager>,
+    edit_log: State<'_, EditLog>,
+) -> Result<RowEdit, String> {
+    let (manager, log) = (db_manager.inner().clone(), edit_log.inner().clone());
+    tokio::task::spawn_blocking(move || log.undo(&manager, &db_path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| format!("Undo failed: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_edit_history(
+    db_path: String,
+    edit_log: State<'_, EditLog>,
+) -> Result<Vec<RowEdit>, String> {
+    Ok(edit_log.history(&db_path))
+}
@@ -1,39 +1,438 @@
+use crate::browse;
+use crate::migrations;
 use crate::models::*;
-use rusqlite::{types::Value, Connection, Result as RusqliteResult};
+use crate::snapshot;
+use rusqlite::{types::Value, Connection, OpenFlags, Result as RusqliteResult};
 use std::collections::HashMap;
 // use anyhow::{Context, Result};
 use anyhow::Context;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use sha2::{Digest, Sha256};
+use std::ops::Deref;
 use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 
+/// Idle read-only connections kept around per database for browsing and comparisons
+const MAX_IDLE_READERS: usize = 4;
+
+/// Registry of connected databases. Cloning is cheap (shared state), and every
+/// database has its own connections, so a slow query on one database never
+/// blocks work on another.
+#[derive(Clone)]
 pub struct DatabaseManager {
-    connections: HashMap<String, Connection>,
-    connected_databases: HashMap<String, DatabaseInfo>,
+    databases: Arc<RwLock<HashMap<String, Arc<ConnectedDatabase>>>>,
+}
+
+/// A connected database: a primary connection (opened with the requested access
+/// mode) plus a pool of read-only connections
+pub struct ConnectedDatabase {
+    path: String,
+    password: String,
+    settings: Option<serde_json::Value>,
+    db_type: DatabaseType,
+    access_mode: AccessMode,
+    info: Mutex<DatabaseInfo>,
+    primary: Mutex<Option<Connection>>,
+    readers: Mutex<Vec<Connection>>,
+    fingerprint: Mutex<Option<FileFingerprint>>,
+}
+
+/// Read-only connection borrowed from a database's pool; returned to the pool on drop
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    database: Arc<ConnectedDatabase>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+            .as_ref()
+            .expect("pooled connection already released")
+    }
 }
 
-#[derive(Debug)]
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            if !self.database.is_connected() {
+                return;
+            }
+            // A statement left a transaction open or a database attached; don't reuse it
+            if !conn.is_autocommit() || has_attached_databases(&conn) {
+                println!("⚠️ Discarding a reader left in a changed state");
+                return;
+            }
+            let mut readers = self.database.readers.lock().unwrap();
+            if readers.len() < MAX_IDLE_READERS {
+                readers.push(conn);
+            }
+        }
+    }
+}
+
+/// A database ATTACHed to another connection; detached again on drop
+pub struct AttachedDatabase<'a> {
+    conn: &'a Connection,
+    schema: String,
+}
+
+impl Drop for AttachedDatabase<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self
+            .conn
+            .execute(&format!("DETACH DATABASE \"{}\"", self.schema), [])
+        {
+            println!("⚠️ Failed to detach {}: {}", self.schema, e);
+        }
+    }
+}
+
+/// On-disk state of a connected database, used to notice changes made outside the app
+#[derive(Debug, Clone, PartialEq)]
+struct FileFingerprint {
+    modified: Option<SystemTime>,
+    size: u64,
+    data_version: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DatabaseType {
     SQLite,    // Regular unencrypted SQLite
     SQLCipher, // Encrypted SQLCipher
 }
 
+impl ConnectedDatabase {
+    fn is_connected(&self) -> bool {
+        self.info.lock().unwrap().is_connected
+    }
+
+    fn info(&self) -> DatabaseInfo {
+        self.info.lock().unwrap().clone()
+    }
+
+    fn reader_mode(&self) -> AccessMode {
+        match self.access_mode {
+            AccessMode::Immutable => AccessMode::Immutable,
+            _ => AccessMode::ReadOnly,
+        }
+    }
+
+    /// Borrow an idle read-only connection or open a new one
+    fn reader(self: &Arc<Self>) -> anyhow::Result<PooledConnection> {
+        if !self.is_connected() {
+            anyhow::bail!("Database not connected");
+        }
+
+        let idle = self.readers.lock().unwrap().pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => open_connection(
+                &self.path,
+                self.db_type,
+                &self.password,
+                self.settings.as_ref(),
+                self.reader_mode(),
+            )?,
+        };
+
+        Ok(PooledConnection {
+            conn: Some(conn),
+            database: Arc::clone(self),
+        })
+    }
+
+    /// ATTACH this database to `conn` as `schema`, read-only. The path and key are
+    /// bound as parameters and the cipher settings are applied to the attached
+    /// schema, so plain and SQLCipher databases can be mixed freely.
+    fn attach_to<'a>(
+        &self,
+        conn: &'a Connection,
+        schema: &str,
+    ) -> anyhow::Result<AttachedDatabase<'a>> {
+        // An empty key explicitly marks the attached database as unencrypted;
+        // otherwise SQLCipher would reuse the main database's key
+        let key = match self.db_type {
+            DatabaseType::SQLCipher => self.password.as_str(),
+            DatabaseType::SQLite => "",
+        };
+
+        let attach = || {
+            conn.execute(
+                &format!("ATTACH DATABASE ?1 AS \"{}\" KEY ?2", schema),
+                [sqlite_uri(&self.path, self.reader_mode()).as_str(), key],
+            )
+        };
+
+        // ATTACH reads the schema right away, so the cipher settings have to be in place
+        // beforehand. SQLCipher only takes them from its process-wide defaults, which are
+        // swapped in (and restored) under a lock.
+        let result = match self.db_type {
+            DatabaseType::SQLCipher => {
+                let _lock = CIPHER_DEFAULTS_LOCK.lock().unwrap();
+                let previous = read_cipher_defaults(conn)?;
+                apply_cipher_params(conn, CipherPragmas::Defaults, self.settings.as_ref())?;
+                let result = attach();
+                restore_cipher_defaults(conn, &previous);
+                result
+            }
+            DatabaseType::SQLite => attach(),
+        };
+        result.with_context(|| format!("Failed to attach {}", self.path))?;
+
+        let attached = AttachedDatabase {
+            conn,
+            schema: schema.to_string(),
+        };
+
+        conn.query_row(
+            &format!("SELECT COUNT(*) FROM \"{}\".sqlite_master", schema),
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .with_context(|| {
+            format!(
+                "Failed to read attached database {} - incorrect password or settings",
+                self.path
+            )
+        })?;
+
+        Ok(attached)
+    }
+
+    fn read_fingerprint(&self, conn: &Connection) -> anyhow::Result<FileFingerprint> {
+        let metadata = std::fs::metadata(&self.path)?;
+        let data_version: i64 = conn.query_row("PRAGMA data_version", [], |row| row.get(0))?;
+
+        Ok(FileFingerprint {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+            data_version,
+        })
+    }
+}
+
 impl DatabaseManager {
     pub fn new() -> Self {
         Self {
-            connections: HashMap::new(),
-            connected_databases: HashMap::new(),
+            databases: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    pub fn get_password(&self, db_path: &str) -> Option<String> {
-        self.connected_databases
+    fn database(&self, db_path: &str) -> anyhow::Result<Arc<ConnectedDatabase>> {
+        self.databases
+            .read()
+            .unwrap()
             .get(db_path)
-            .and_then(|info| info.password.clone())
+            .cloned()
+            .context("Database not connected")
+    }
+
+    /// Borrow a read-only connection to a connected database
+    pub fn reader(&self, db_path: &str) -> anyhow::Result<PooledConnection> {
+        self.database(db_path)?.reader()
+    }
+
+    /// Open a new read-write connection for an explicit write (apply patch, migration).
+    /// This is the only way the app writes to a database, whatever its access mode.
+    pub fn open_writable(&self, db_path: &str) -> anyhow::Result<Connection> {
+        let database = self.database(db_path)?;
+        open_connection(
+            &database.path,
+            database.db_type,
+            &database.password,
+            database.settings.as_ref(),
+            AccessMode::ReadWrite,
+        )
+    }
+
+    pub fn access_mode(&self, db_path: &str) -> Option<AccessMode> {
+        self.database(db_path).ok().map(|db| db.access_mode)
+    }
+
+    pub fn get_password(&self, db_path: &str) -> Option<String> {
+        self.database(db_path).ok().map(|db| db.password.clone())
+    }
+
+    /// Set (or clear) the display alias of a connected database
+    pub fn set_alias(&self, db_path: &str, alias: Option<String>) -> Option<DatabaseInfo> {
+        let database = self.database(db_path).ok()?;
+        let mut info = database.info.lock().unwrap();
+        info.alias = alias;
+        Some(info.clone())
     }
 
     /// Disconnect a database to force reconnection (useful after schema changes)
-    pub fn disconnect(&mut self, db_path: &str) {
-        self.connections.remove(db_path);
-        // Keep connected_databases entry so we remember the password
+    pub fn disconnect(&self, db_path: &str) {
+        // Keep the registry entry so we remember the password
+        if let Ok(database) = self.database(db_path) {
+            database.info.lock().unwrap().is_connected = false;
+            *database.primary.lock().unwrap() = None;
+            database.readers.lock().unwrap().clear();
+            *database.fingerprint.lock().unwrap() = None;
+        }
+    }
+
+    /// Close a database and forget it entirely (password, settings, alias)
+    pub fn close(&self, db_path: &str) -> anyhow::Result<()> {
+        let database = self
+            .databases
+            .write()
+            .unwrap()
+            .remove(db_path)
+            .with_context(|| format!("Database not connected: {}", db_path))?;
+
+        database.info.lock().unwrap().is_connected = false;
+        database.readers.lock().unwrap().clear();
+        let primary = database.primary.lock().unwrap().take();
+
+        match primary {
+            Some(conn) => conn
+                .close()
+                .map_err(|(_, e)| anyhow::anyhow!("Failed to close {}: {}", db_path, e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Close every open connection (called on app exit)
+    pub fn close_all(&self) {
+        let paths: Vec<String> = self.databases.read().unwrap().keys().cloned().collect();
+        for path in paths {
+            if let Err(e) = self.close(&path) {
+                println!("⚠️ {}", e);
+            }
+        }
+    }
+
+    pub fn list_connections(&self) -> Vec<DatabaseInfo> {
+        let mut databases: Vec<DatabaseInfo> = self
+            .databases
+            .read()
+            .unwrap()
+            .values()
+            .map(|db| db.info())
+            .collect();
+        databases.sort_by(|a, b| a.path.cmp(&b.path));
+        databases
+    }
+
+    /// Reopen a database with the password and settings it was connected with
+    pub fn reconnect(&self, db_path: &str) -> anyhow::Result<DatabaseInfo> {
+        let database = self.database(db_path)?;
+        let alias = database.info().alias;
+
+        self.disconnect(db_path);
+        self.connect_database(
+            db_path,
+            &database.password,
+            database.settings.clone(),
+            database.access_mode,
+        )?;
+
+        self.set_alias(db_path, alias)
+            .context("Database not connected")
+    }
+
+    /// Compare every open connection against its last known on-disk state.
+    /// Files replaced underneath us are reopened, deleted files are closed.
+    pub fn check_for_changes(&self) -> Vec<DatabaseChangeEvent> {
+        let mut events = Vec::new();
+        let databases: Vec<Arc<ConnectedDatabase>> =
+            self.databases.read().unwrap().values().cloned().collect();
+
+        for database in databases {
+            let path = database.path.clone();
+
+            let current = {
+                // Skip databases whose primary connection is busy; checked on the next tick
+                let Ok(primary) = database.primary.try_lock() else {
+                    continue;
+                };
+                let Some(conn) = primary.as_ref() else {
+                    continue;
+                };
+                database.read_fingerprint(conn)
+            };
+
+            let current = match current {
+                Ok(fingerprint) => fingerprint,
+                Err(_) if !Path::new(&path).exists() => {
+                    println!("⚠️ Database file removed: {}", path);
+                    self.disconnect(&path);
+                    events.push(DatabaseChangeEvent {
+                        path,
+                        kind: DatabaseChangeKind::Deleted,
+                        database: None,
+                        error: None,
+                    });
+                    continue;
+                }
+                Err(e) => {
+                    println!("⚠️ Could not check {} for changes: {}", path, e);
+                    continue;
+                }
+            };
+
+            let previous = database
+                .fingerprint
+                .lock()
+                .unwrap()
+                .replace(current.clone());
+            let Some(previous) = previous else {
+                continue;
+            };
+
+            if current == previous {
+                continue;
+            }
+
+            let file_changed =
+                current.modified != previous.modified || current.size != previous.size;
+
+            if current.data_version != previous.data_version {
+                // Another connection committed - our connections already see the new data
+                let database = self.refresh_table_count(&path);
+                events.push(DatabaseChangeEvent {
+                    path,
+                    kind: DatabaseChangeKind::Modified,
+                    database,
+                    error: None,
+                });
+            } else if file_changed {
+                // File was replaced or rewritten outside SQLite - reopen to see it
+                println!("🔄 Database file changed on disk, reconnecting: {}", path);
+                let (database, error) = match self.reconnect(&path) {
+                    Ok(info) => (Some(info), None),
+                    Err(e) => (None, Some(format!("Reconnect failed: {}", e))),
+                };
+                events.push(DatabaseChangeEvent {
+                    path,
+                    kind: DatabaseChangeKind::Replaced,
+                    database,
+                    error,
+                });
+            }
+        }
+
+        events
+    }
+
+    fn refresh_table_count(&self, db_path: &str) -> Option<DatabaseInfo> {
+        let database = self.database(db_path).ok()?;
+        let conn = database.reader().ok()?;
+        let table_count: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table';",
+                [],
+                |row| row.get(0),
+            )
+            .ok()?;
+
+        let mut info = database.info.lock().unwrap();
+        info.table_count = table_count;
+        Some(info.clone())
     }
 
     /// Detect if a database is encrypted (SQLCipher) or regular SQLite
@@ -41,8 +440,8 @@ impl DatabaseManager {
         // Use Path for cross-platform handling
         let db_path = Path::new(path);
 
-        // Try to open as regular SQLite first
-        match Connection::open(db_path) {
+        // Try to open as regular SQLite first (read-only, so a missing file is never created)
+        match Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY) {
             Ok(conn) => {
                 // Try to read sqlite_master without password
                 match conn.query_row("SELECT COUNT(*) FROM sqlite_master;", [], |row| {
@@ -161,150 +560,105 @@ impl DatabaseManager {
     /// Connect to database with automatic type detection
     ///
     pub fn connect_database(
-        &mut self,
+        &self,
         path: &str,
         password: &str,
         settings: Option<serde_json::Value>, // Add this parameter
+        access_mode: AccessMode,
     ) -> anyhow::Result<DatabaseInfo> {
         println!("Attempting to connect to database: {}", path);
 
         let db_path = Path::new(path);
         let db_type = self.detect_database_type(path)?;
 
-        match db_type {
-            DatabaseType::SQLite => {
-                // SQLite connection (no password/settings needed)
-                let conn = Connection::open(db_path)
-                    .with_context(|| format!("Failed to open database file: {}", path))?;
-
-                let table_count: i32 = conn.query_row(
-                    "SELECT COUNT(*) FROM sqlite_master WHERE type='table';",
-                    [],
-                    |row| row.get(0),
-                )?;
-
-                let db_info = DatabaseInfo {
-                    path: path.to_string(),
-                    name: db_path
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string(),
-                    table_count,
-                    is_connected: true,
-                    alias: None,
-                    password: Some(password.to_string()),
-                };
-
-                self.connections.insert(path.to_string(), conn);
-                self.connected_databases
-                    .insert(path.to_string(), db_info.clone());
-                Ok(db_info)
-            }
-            DatabaseType::SQLCipher => {
-                let conn = Connection::open(db_path)
-                    .with_context(|| format!("Failed to open database file: {}", path))?;
-
-                // Set password
-                conn.pragma_update(None, "key", password)
-                    .with_context(|| "Failed to set encryption key")?;
-
-                // Apply settings from user or use defaults
-                if let Some(s) = settings {
-                    // Extract settings from JSON
-                    let page_size = s["page_size"].as_str().unwrap_or("4096");
-                    let kdf_iter = s["kdf_iterations"].as_str().unwrap_or("256000");
-                    let hmac_algo = s["hmac_algorithm"].as_str().unwrap_or("HMAC_SHA512");
-                    let kdf_algo = s["kdf_algorithm"].as_str().unwrap_or("PBKDF2_HMAC_SHA512");
-
-                    println!(
-                        "📝 Applying user settings: page_size={}, kdf_iter={}",
-                        page_size, kdf_iter
-                    );
-
-                    conn.pragma_update(None, "cipher_page_size", page_size)
-                        .with_context(|| "Failed to set page size")?;
-
-                    conn.pragma_update(None, "kdf_iter", kdf_iter)
-                        .with_context(|| "Failed to set KDF iterations")?;
-
-                    conn.pragma_update(None, "cipher_hmac_algorithm", hmac_algo)
-                        .with_context(|| "Failed to set HMAC algorithm")?;
-
-                    conn.pragma_update(None, "cipher_kdf_algorithm", kdf_algo)
-                        .with_context(|| "Failed to set KDF algorithm")?;
-                } else {
-                    // Use default settings (SQLCipher 4 defaults with SHA512)
-                    println!("📝 Applying default settings (SQLCipher 4 with SHA512)");
-
-                    conn.pragma_update(None, "cipher_page_size", "4096")
-                        .with_context(|| "Failed to set page size")?;
-
-                    conn.pragma_update(None, "kdf_iter", "256000")
-                        .with_context(|| "Failed to set KDF iterations")?;
-
-                    conn.pragma_update(None, "cipher_hmac_algorithm", "HMAC_SHA512")
-                        .with_context(|| "Failed to set HMAC algorithm")?;
-
-                    conn.pragma_update(None, "cipher_kdf_algorithm", "PBKDF2_HMAC_SHA512")
-                        .with_context(|| "Failed to set KDF algorithm")?;
+        let conn = open_connection(path, db_type, password, settings.as_ref(), access_mode)?;
+
+        // Verify connection
+        let table_count: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table';",
+                [],
+                |row| row.get(0),
+            )
+            .with_context(|| match db_type {
+                DatabaseType::SQLite => "Failed to read database".to_string(),
+                DatabaseType::SQLCipher => {
+                    "Key verification failed - incorrect password or settings".to_string()
                 }
+            })?;
 
-                println!("✅ SQLCipher settings applied");
-
-                // Verify connection
-                let table_count: i32 = conn
-                    .query_row(
-                        "SELECT COUNT(*) FROM sqlite_master WHERE type='table';",
-                        [],
-                        |row| row.get(0),
-                    )
-                    .with_context(|| "Key verification failed - incorrect password or settings")?;
-
-                println!("✅ SQLCipher key accepted! Found {} tables.", table_count);
-
-                let db_name = db_path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-
-                let db_info = DatabaseInfo {
-                    path: path.to_string(),
-                    name: db_name,
-                    table_count,
-                    is_connected: true,
-                    alias: None,
-                    password: Some(password.to_string()),
-                };
+        if db_type == DatabaseType::SQLCipher {
+            println!("✅ SQLCipher key accepted! Found {} tables.", table_count);
+        }
 
-                self.connections.insert(path.to_string(), conn);
-                self.connected_databases
-                    .insert(path.to_string(), db_info.clone());
-                println!("✅ Database connection stored successfully");
+        let db_info = DatabaseInfo {
+            path: path.to_string(),
+            name: db_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            table_count,
+            is_connected: true,
+            alias: None,
+            password: Some(password.to_string()),
+            access_mode,
+        };
 
-                Ok(db_info)
-            }
+        let database = ConnectedDatabase {
+            path: path.to_string(),
+            password: password.to_string(),
+            settings,
+            db_type,
+            access_mode,
+            info: Mutex::new(db_info.clone()),
+            fingerprint: Mutex::new(None),
+            primary: Mutex::new(None),
+            readers: Mutex::new(Vec::new()),
+        };
+        match database.read_fingerprint(&conn) {
+            Ok(fingerprint) => *database.fingerprint.lock().unwrap() = Some(fingerprint),
+            Err(e) => println!("⚠️ Could not fingerprint {}: {}", path, e),
         }
+        *database.primary.lock().unwrap() = Some(conn);
+
+        // Replacing an existing entry drops its connections once in-flight work finishes
+        self.databases
+            .write()
+            .unwrap()
+            .insert(path.to_string(), Arc::new(database));
+        println!("✅ Database connection stored successfully");
+
+        Ok(db_info)
     }
 
     pub fn get_tables(&self, db_path: &str) -> anyhow::Result<Vec<TableInfo>> {
-        let conn = self
-            .connections
-            .get(db_path)
-            .context("Database not connected")?;
+        let conn = self.reader(db_path)?;
 
         let mut stmt = conn.prepare(
-            "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
+            "SELECT name, sql FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
         )?;
 
-        let table_names: Vec<String> = stmt
-            .query_map([], |row| Ok(row.get::<_, String>(0)?))?
+        let table_names: Vec<(String, Option<String>)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
             .collect::<RusqliteResult<Vec<_>>>()?;
 
+        // Shadow tables hold the data of a virtual table (FTS5 `_data`, R*Tree `_node`, ...)
+        // and come and go with it. Older SQLite versions can't list them.
+        let shadow_tables: std::collections::HashSet<String> = conn
+            .prepare("SELECT name FROM pragma_table_list WHERE schema = 'main' AND type = 'shadow'")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?
+                    .collect::<RusqliteResult<_>>()
+            })
+            .unwrap_or_default();
+
         let mut tables = Vec::new();
 
-        for table_name in table_names {
+        for (table_name, sql) in table_names {
+            if shadow_tables.contains(&table_name) {
+                continue;
+            }
             // Get row count safely
             let row_count: i64 = conn
                 .query_row(
@@ -315,24 +669,30 @@ impl DatabaseManager {
                 .unwrap_or(0);
 
             // Get column info
-            let columns = self.get_table_columns(conn, &table_name)?;
+            let columns = self.get_schema_columns(&conn, &table_name)?;
+            let (options, virtual_module) = table_options(sql.as_deref().unwrap_or_default());
 
             tables.push(TableInfo {
                 name: table_name,
                 row_count,
                 columns,
+                options,
+                virtual_module,
             });
         }
 
         Ok(tables)
     }
 
-    fn get_table_columns(
+    pub(crate) fn get_table_columns(
         &self,
         conn: &Connection,
         table_name: &str,
     ) -> anyhow::Result<Vec<ColumnInfo>> {
-        let mut stmt = conn.prepare(&format!("PRAGMA table_info(\"{}\")", table_name))?;
+        let mut stmt = conn.prepare(&format!(
+            "PRAGMA table_info({})",
+            browse::quote_identifier(table_name)
+        ))?;
 
         let columns: Vec<ColumnInfo> = stmt
             .query_map([], |row| {
@@ -341,7 +701,42 @@ impl DatabaseManager {
                     data_type: row.get::<_, String>(2)?,
                     is_nullable: row.get::<_, i32>(3)? == 0,
                     default_value: row.get::<_, Option<String>>(4)?,
-                    is_primary_key: row.get::<_, i32>(5)? == 1,
+                    // pk is the column's 1-based position in the primary key, 0 if not part of it
+                    is_primary_key: row.get::<_, i32>(5)? > 0,
+                    generated: None,
+                })
+            })?
+            .collect::<RusqliteResult<Vec<_>>>()?;
+
+        Ok(columns)
+    }
+
+    /// Columns as the schema diff sees them: those of `get_table_columns` plus generated
+    /// columns, which can't be written to and so are left out there
+    fn get_schema_columns(
+        &self,
+        conn: &Connection,
+        table_name: &str,
+    ) -> anyhow::Result<Vec<ColumnInfo>> {
+        // hidden: 1 for hidden columns of virtual tables, 2/3 for virtual/stored generated ones
+        let mut stmt = conn.prepare(
+            "SELECT name, type, \"notnull\", dflt_value, pk, hidden \
+             FROM pragma_table_xinfo(?1) WHERE hidden <> 1 ORDER BY cid",
+        )?;
+
+        let columns: Vec<ColumnInfo> = stmt
+            .query_map([table_name], |row| {
+                Ok(ColumnInfo {
+                    name: row.get(0)?,
+                    data_type: row.get(1)?,
+                    is_nullable: row.get::<_, i32>(2)? == 0,
+                    default_value: row.get(3)?,
+                    is_primary_key: row.get::<_, i32>(4)? > 0,
+                    generated: match row.get::<_, i32>(5)? {
+                        2 => Some("VIRTUAL".to_string()),
+                        3 => Some("STORED".to_string()),
+                        _ => None,
+                    },
                 })
             })?
             .collect::<RusqliteResult<Vec<_>>>()?;
@@ -408,68 +803,85 @@ impl DatabaseManager {
     // REPLACE the existing get_table_data function in database.rs (around line 303)
     // with this version that supports OFFSET
 
+    /// A page of a table on a pooled reader, interrupted if it runs longer than `timeout`
     pub fn get_table_data(
         &self,
         db_path: &str,
         table_name: &str,
+        query: Option<&TableQuery>,
         limit: Option<i64>,
         offset: Option<i64>, // ← NEW PARAMETER
+        timeout: Duration,
     ) -> anyhow::Result<TableData> {
-        let conn = self
-            .connections
-            .get(db_path)
-            .context("Database not connected")?;
+        let conn = self.reader(db_path)?;
+        match interrupt_after(&conn, timeout, || {
+            self.read_table_data(&conn, table_name, query, limit, offset)
+        }) {
+            Err(e) if is_interrupted(&e) => anyhow::bail!(
+                "Query timeout - table '{}' took too long to fetch. Try using a smaller row limit.",
+                table_name
+            ),
+            result => result,
+        }
+    }
 
+    fn read_table_data(
+        &self,
+        conn: &Connection,
+        table_name: &str,
+        query: Option<&TableQuery>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> anyhow::Result<TableData> {
         // Get column names
         let columns = self.get_table_columns(conn, table_name)?;
+        if columns.is_empty() {
+            anyhow::bail!("Table '{}' not found", table_name);
+        }
         let column_names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
 
-        // Get total count
+        // Filters, search and sort order compile to parameterised SQL
+        let compiled = match query {
+            Some(query) => browse::compile(query, &columns)?,
+            None => browse::CompiledQuery::default(),
+        };
+        let table = browse::quote_identifier(table_name);
+
+        // Get total count (of the filtered rows)
         let total_count: i64 = conn.query_row(
-            &format!("SELECT COUNT(*) FROM \"{}\"", table_name),
-            [],
+            &format!("SELECT COUNT(*) FROM {}{}", table, compiled.where_clause),
+            rusqlite::params_from_iter(&compiled.params),
             |row| row.get(0),
         )?;
 
         // Build dynamic SELECT query using column names to ensure order
         let col_list = column_names
             .iter()
-            .map(|c| format!("\"{}\"", c))
+            .map(|c| browse::quote_identifier(c))
             .collect::<Vec<_>>()
             .join(", ");
 
-        // ← UPDATED: Build query with OFFSET support
-        let query = match (limit, offset) {
-            (Some(l), Some(o)) => format!(
-                "SELECT {} FROM \"{}\" LIMIT {} OFFSET {}",
-                col_list, table_name, l, o
-            ),
-            (Some(l), None) => format!("SELECT {} FROM \"{}\" LIMIT {}", col_list, table_name, l),
-            (None, Some(o)) => format!("SELECT {} FROM \"{}\" OFFSET {}", col_list, table_name, o),
-            (None, None) => format!("SELECT {} FROM \"{}\"", col_list, table_name),
-        };
+        let mut query = format!(
+            "SELECT {} FROM {}{}{}",
+            col_list, table, compiled.where_clause, compiled.order_by
+        );
+        // ← UPDATED: OFFSET support (SQLite needs a LIMIT before OFFSET, -1 = no limit)
+        match (limit, offset) {
+            (Some(l), Some(o)) => query.push_str(&format!(" LIMIT {} OFFSET {}", l, o)),
+            (Some(l), None) => query.push_str(&format!(" LIMIT {}", l)),
+            (None, Some(o)) => query.push_str(&format!(" LIMIT -1 OFFSET {}", o)),
+            (None, None) => {}
+        }
 
         let mut stmt = conn.prepare(&query)?;
         let column_count = stmt.column_count();
 
         // Return rows as arrays (your existing format)
         let rows: Vec<Vec<serde_json::Value>> = stmt
-            .query_map([], |row| {
+            .query_map(rusqlite::params_from_iter(&compiled.params), |row| {
                 let mut row_data = Vec::new();
                 for i in 0..column_count {
-                    let value: Value = row.get(i)?;
-                    let json_value = match value {
-                        Value::Null => serde_json::Value::Null,
-                        Value::Integer(i) => serde_json::Value::Number(i.into()),
-                        Value::Real(f) => serde_json::Value::Number(
-                            serde_json::Number::from_f64(f).unwrap_or(0.into()),
-                        ),
-                        Value::Text(s) => serde_json::Value::String(s),
-                        Value::Blob(b) => {
-                            serde_json::Value::String(format!("<BLOB {} bytes>", b.len()))
-                        }
-                    };
-                    row_data.push(json_value);
+                    row_data.push(value_to_json(row.get(i)?));
                 }
                 Ok(row_data)
             })?
@@ -481,13 +893,193 @@ impl DatabaseManager {
             total_count,
         })
     }
+
+    /// Browse a table page by page. Pages are found by seeking past the previous page's
+    /// last row (rowid or primary key), so deep pages cost the same as the first one;
+    /// tables without a usable key fall back to OFFSET. Interrupted if it runs longer
+    /// than `timeout`.
+    pub fn browse_table_data(
+        &self,
+        db_path: &str,
+        table_name: &str,
+        query: Option<&TableQuery>,
+        limit: i64,
+        cursor: Option<&str>,
+        timeout: Duration,
+    ) -> anyhow::Result<TablePage> {
+        let conn = self.reader(db_path)?;
+        match interrupt_after(&conn, timeout, || {
+            self.read_table_page(&conn, table_name, query, limit, cursor)
+        }) {
+            Err(e) if is_interrupted(&e) => anyhow::bail!(
+                "Query timeout - table '{}' took too long to fetch",
+                table_name
+            ),
+            result => result,
+        }
+    }
+
+    fn read_table_page(
+        &self,
+        conn: &Connection,
+        table_name: &str,
+        query: Option<&TableQuery>,
+        limit: i64,
+        cursor: Option<&str>,
+    ) -> anyhow::Result<TablePage> {
+        let columns = self.get_table_columns(conn, table_name)?;
+        if columns.is_empty() {
+            anyhow::bail!("Table '{}' not found", table_name);
+        }
+        let column_names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+
+        let compiled = match query {
+            Some(query) => browse::compile(query, &columns)?,
+            None => browse::CompiledQuery::default(),
+        };
+        let table = browse::quote_identifier(table_name);
+        let keyset = browse::Keyset::plan(conn, &table, &columns, &compiled);
+        let signature = match &keyset {
+            Some(keyset) => keyset.signature.clone(),
+            None => browse::query_signature(&table, &compiled),
+        };
+
+        let position = cursor
+            .map(|cursor| browse::decode_cursor(cursor, &signature))
+            .transpose()?;
+
+        // Counting is as expensive as a full scan, so only the first page pays for it
+        let total_count = match position {
+            Some(_) => None,
+            None => Some(conn.query_row(
+                &format!("SELECT COUNT(*) FROM {}{}", table, compiled.where_clause),
+                rusqlite::params_from_iter(&compiled.params),
+                |row| row.get::<_, i64>(0),
+            )?),
+        };
+
+        let mut select_list: Vec<String> = column_names
+            .iter()
+            .map(|c| browse::quote_identifier(c))
+            .collect();
+        let mut params = compiled.params.clone();
+
+        // One extra row tells us whether there is a next page
+        let sql = match (&keyset, &position) {
+            (Some(keyset), position) => {
+                // The sort values are selected again after the visible columns to build the cursor
+                select_list.extend(keyset.sort.iter().map(|(column, _)| column.clone()));
+                let where_clause = match position {
+                    Some(browse::PagePosition::After(after)) => browse::and_where(
+                        &compiled.where_clause,
+                        &keyset.after_condition(after, &mut params),
+                    ),
+                    _ => compiled.where_clause.clone(),
+                };
+                format!(
+                    "SELECT {} FROM {}{}{} LIMIT {}",
+                    select_list.join(", "),
+                    table,
+                    where_clause,
+                    keyset.order_by(),
+                    limit + 1
+                )
+            }
+            (None, position) => {
+                let offset = match position {
+                    Some(browse::PagePosition::Offset(offset)) => *offset,
+                    _ => 0,
+                };
+                format!(
+                    "SELECT {} FROM {}{}{} LIMIT {} OFFSET {}",
+                    select_list.join(", "),
+                    table,
+                    compiled.where_clause,
+                    compiled.order_by,
+                    limit + 1,
+                    offset
+                )
+            }
+        };
+
+        let mut stmt = conn.prepare(&sql)?;
+        let visible = column_names.len();
+        let total_columns = stmt.column_count();
+
+        let mut page: Vec<Vec<Value>> = stmt
+            .query_map(rusqlite::params_from_iter(&params), |row| {
+                (0..total_columns).map(|i| row.get::<_, Value>(i)).collect()
+            })?
+            .collect::<RusqliteResult<Vec<_>>>()?;
+
+        let has_more = page.len() as i64 > limit;
+        page.truncate(limit.max(0) as usize);
+
+        let next_cursor = match (has_more, &keyset) {
+            (false, _) => None,
+            (true, Some(_)) => page.last().map(|last| {
+                browse::encode_cursor(
+                    &signature,
+                    browse::PagePosition::After(last[visible..].to_vec()),
+                )
+            }),
+            (true, None) => {
+                let offset = match position {
+                    Some(browse::PagePosition::Offset(offset)) => offset,
+                    _ => 0,
+                };
+                Some(browse::encode_cursor(
+                    &signature,
+                    browse::PagePosition::Offset(offset + page.len() as i64),
+                ))
+            }
+        };
+
+        Ok(TablePage {
+            columns: column_names,
+            rows: page
+                .into_iter()
+                .map(|row| row.into_iter().take(visible).map(value_to_json).collect())
+                .collect(),
+            total_count,
+            next_cursor,
+        })
+    }
+    /// Run a read-only statement from the SQL console on a pooled reader. The statement
+    /// is interrupted (`sqlite3_interrupt`) if it runs longer than `timeout`.
+    pub fn execute_query(
+        &self,
+        db_path: &str,
+        sql: &str,
+        max_rows: usize,
+        timeout: Duration,
+        explain: bool,
+    ) -> anyhow::Result<QueryResult> {
+        let conn = self.reader(db_path)?;
+        let started = Instant::now();
+
+        match interrupt_after(&conn, timeout, || run_query(&conn, sql, max_rows, explain)) {
+            Err(e) if is_interrupted(&e) => anyhow::bail!(
+                "Query cancelled after {} seconds - try a narrower query or a higher timeout",
+                timeout.as_secs()
+            ),
+            Err(e) => Err(e),
+            Ok(mut result) => {
+                result.elapsed_ms = started.elapsed().as_millis() as u64;
+                Ok(result)
+            }
+        }
+    }
+
     pub fn compare_schemas(
         &self,
         db1_path: &str,
         db2_path: &str,
+        filter: &ComparisonFilter,
+        rules: &ComparisonRules,
     ) -> anyhow::Result<SchemaComparison> {
-        let tables1 = self.get_tables(db1_path)?;
-        let tables2 = self.get_tables(db2_path)?;
+        let tables1 = filter.apply(self.tables_or_snapshot(db1_path)?);
+        let tables2 = filter.apply(self.tables_or_snapshot(db2_path)?);
 
         let table1_names: std::collections::HashSet<String> =
             tables1.iter().map(|t| t.name.clone()).collect();
@@ -506,10 +1098,15 @@ impl DatabaseManager {
             let table1 = tables1.iter().find(|t| t.name == table_name).unwrap();
             let table2 = tables2.iter().find(|t| t.name == table_name).unwrap();
 
-            if self.tables_are_identical(&table1, &table2) {
+            let diff = self.compare_tables(table1, table2, rules);
+            if diff.added_columns.is_empty()
+                && diff.removed_columns.is_empty()
+                && diff.modified_columns.is_empty()
+                && !diff.column_order_changed
+                && diff.option_changes.is_empty()
+            {
                 identical_tables.push(table_name);
             } else {
-                let diff = self.compare_tables(&table1, &table2);
                 modified_tables.push(diff);
             }
         }
@@ -524,6 +1121,120 @@ impl DatabaseManager {
         })
     }
 
+    /// Tables of a connected database, or of a snapshot file if `path` names one
+    fn tables_or_snapshot(&self, path: &str) -> anyhow::Result<Vec<TableInfo>> {
+        if self.is_snapshot(path) {
+            return Ok(snapshot::load_snapshot(path)?.tables);
+        }
+        self.get_tables(path)
+    }
+
+    /// A connected database always wins over a snapshot of the same name
+    fn is_snapshot(&self, path: &str) -> bool {
+        snapshot::is_snapshot_path(path) && self.database(path).is_err()
+    }
+
+    /// SHA-256 of the schema as SQLite reports it: columns, keys, unique constraints and
+    /// foreign keys of every table, the CHECK constraints, collations and generated
+    /// column expressions of its `CREATE` statement, plus the SQL of indexes, views and
+    /// triggers with
+    /// formatting normalised. The same schema fingerprints the same however its
+    /// `CREATE` statements were written or rewritten by `ALTER TABLE`. Internal tables
+    /// and the migrations table are left out.
+    pub(crate) fn schema_fingerprint(&self, conn: &Connection) -> anyhow::Result<String> {
+        let mut stmt = conn.prepare(
+            "SELECT type, name, sql FROM sqlite_master \
+             WHERE name NOT LIKE 'sqlite_%' AND name <> ?1 ORDER BY type, lower(name)",
+        )?;
+        let objects = stmt
+            .query_map([migrations::MIGRATIONS_TABLE], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<RusqliteResult<Vec<_>>>()?;
+
+        let mut hasher = Sha256::new();
+        let mut feed = |parts: &[String]| {
+            for part in parts {
+                hasher.update(part.as_bytes());
+                hasher.update([0]);
+            }
+            hasher.update([1]);
+        };
+        for (kind, name, sql) in objects {
+            feed(&[kind.clone(), name.to_lowercase()]);
+            if kind != "table" {
+                feed(&[canonical_sql(sql.as_deref().unwrap_or_default())]);
+                continue;
+            }
+            let (mut options, virtual_module) = table_options(sql.as_deref().unwrap_or_default());
+            options.extend(virtual_module);
+            feed(&options);
+
+            let pragma_rows = |sql: &str, arg: &str| -> anyhow::Result<Vec<Vec<String>>> {
+                let mut stmt = conn.prepare(sql)?;
+                let columns = stmt.column_count();
+                let rows = stmt
+                    .query_map([arg], |row| {
+                        (0..columns)
+                            .map(|i| {
+                                Ok(match row.get::<_, Value>(i)? {
+                                    Value::Null => "NULL".to_string(),
+                                    Value::Integer(n) => n.to_string(),
+                                    Value::Real(f) => f.to_string(),
+                                    Value::Text(s) => s,
+                                    Value::Blob(b) => BASE64_STANDARD.encode(b),
+                                })
+                            })
+                            .collect()
+                    })?
+                    .collect::<RusqliteResult<Vec<_>>>()?;
+                Ok(rows)
+            };
+
+            for column in pragma_rows(
+                "SELECT name, upper(type), \"notnull\", dflt_value, pk, hidden \
+                 FROM pragma_table_xinfo(?1) ORDER BY cid",
+                &name,
+            )? {
+                feed(&column);
+            }
+            for key in pragma_rows(
+                "SELECT lower(\"table\"), \"from\", \"to\", on_update, on_delete, \"match\" \
+                 FROM pragma_foreign_key_list(?1) ORDER BY id, seq",
+                &name,
+            )? {
+                feed(&key);
+            }
+            // Explicit indexes are covered by their SQL; these come from the table itself
+            let mut constraints = Vec::new();
+            for index in pragma_rows(
+                "SELECT name, origin, \"unique\" FROM pragma_index_list(?1) WHERE origin <> 'c'",
+                &name,
+            )? {
+                let columns = pragma_rows(
+                    "SELECT name, coll, \"desc\" FROM pragma_index_xinfo(?1) WHERE key ORDER BY seqno",
+                    &index[0],
+                )?;
+                let mut constraint = vec![index[1].clone(), index[2].clone()];
+                constraint.extend(columns.into_iter().flatten());
+                constraints.push(constraint);
+            }
+            constraints.sort();
+            for constraint in constraints {
+                feed(&constraint);
+            }
+            // Only in the CREATE statement: CHECK constraints, collations and the
+            // expressions of generated columns
+            feed(&column_clauses(sql.as_deref().unwrap_or_default()));
+        }
+
+        Ok(hex(&hasher.finalize()))
+    }
+
     // Fast data comparison using SQL joins - handles 500K+ rows easily
     pub fn compare_table_data_fast(
         &self,
@@ -532,17 +1243,22 @@ impl DatabaseManager {
         table_name: &str,
         primary_key: &str,
     ) -> anyhow::Result<DataComparisonResult> {
-        let conn1 = self
-            .connections
-            .get(db1_path)
-            .context("Database 1 not connected")?;
-        let _conn2 = self
-            .connections
-            .get(db2_path)
+        // Snapshots only have row hashes (keyed by their primary key) to compare
+        let (snapshot1, snapshot2) = (self.is_snapshot(db1_path), self.is_snapshot(db2_path));
+        if snapshot1 || snapshot2 {
+            return snapshot::compare_table_data(
+                self, db1_path, snapshot1, db2_path, snapshot2, table_name,
+            );
+        }
+
+        let conn1 = self.reader(db1_path).context("Database 1 not connected")?;
+        let database2 = self
+            .database(db2_path)
             .context("Database 2 not connected")?;
 
-        // Attach second database to first connection
-        conn1.execute(&format!("ATTACH DATABASE '{}' AS db2", db2_path), [])?;
+        // Attach second database to first connection (read-only, with its own key);
+        // the guard detaches it again even if one of the queries below fails
+        let _attached = database2.attach_to(&conn1, "db2")?;
 
         // Get total row counts (fast)
         let count1: i64 =
@@ -588,9 +1304,6 @@ impl DatabaseManager {
         // This is an approximation - exact comparison would need column-by-column check
         let potentially_modified = (count1 - deleted_count).min(count2 - inserted_count);
 
-        // Detach database
-        conn1.execute("DETACH DATABASE db2", [])?;
-
         Ok(DataComparisonResult {
             table_name: table_name.to_string(),
             total_rows_db1: count1,
@@ -602,25 +1315,12 @@ impl DatabaseManager {
         })
     }
 
-    fn tables_are_identical(&self, table1: &TableInfo, table2: &TableInfo) -> bool {
-        if table1.columns.len() != table2.columns.len() {
-            return false;
-        }
-
-        for (col1, col2) in table1.columns.iter().zip(table2.columns.iter()) {
-            if col1.name != col2.name
-                || col1.data_type != col2.data_type
-                || col1.is_nullable != col2.is_nullable
-                || col1.is_primary_key != col2.is_primary_key
-            {
-                return false;
-            }
-        }
-
-        true
-    }
-
-    fn compare_tables(&self, table1: &TableInfo, table2: &TableInfo) -> TableDiff {
+    fn compare_tables(
+        &self,
+        table1: &TableInfo,
+        table2: &TableInfo,
+        rules: &ComparisonRules,
+    ) -> TableDiff {
         let col1_names: std::collections::HashSet<String> =
             table1.columns.iter().map(|c| c.name.clone()).collect();
         let col2_names: std::collections::HashSet<String> =
@@ -652,7 +1352,7 @@ impl DatabaseManager {
 
             let mut changes = Vec::new();
 
-            if c1.data_type.to_lowercase() != c2.data_type.to_lowercase() {
+            if !rules.types_equal(&c1.data_type, &c2.data_type) {
                 changes.push(format!("type: {} -> {}", c1.data_type, c2.data_type));
             }
             if c1.is_nullable != c2.is_nullable {
@@ -668,7 +1368,18 @@ impl DatabaseManager {
                 ));
             }
             if c1.default_value != c2.default_value {
-                changes.push("default changed".to_string());
+                changes.push(format!(
+                    "default: {} -> {}",
+                    c1.default_value.as_deref().unwrap_or("none"),
+                    c2.default_value.as_deref().unwrap_or("none")
+                ));
+            }
+            if c1.generated != c2.generated {
+                changes.push(format!(
+                    "generated: {} -> {}",
+                    c1.generated.as_deref().unwrap_or("no"),
+                    c2.generated.as_deref().unwrap_or("no")
+                ));
             }
 
             if !changes.is_empty() {
@@ -676,16 +1387,735 @@ impl DatabaseManager {
                     column_name: name,
                     old_type: c1.data_type.clone(),
                     new_type: c2.data_type.clone(),
+                    old_default: c1.default_value.clone(),
+                    new_default: c2.default_value.clone(),
                     changes,
                 });
             }
         }
 
+        // Order of the shared columns only; added and removed ones don't shift it
+        let order1 = table1
+            .columns
+            .iter()
+            .filter(|c| col2_names.contains(&c.name));
+        let order2 = table2
+            .columns
+            .iter()
+            .filter(|c| col1_names.contains(&c.name));
+        let column_order_changed =
+            !rules.ignore_column_order && !order1.map(|c| &c.name).eq(order2.map(|c| &c.name));
+
+        let mut option_changes = Vec::new();
+        for option in ["STRICT", "WITHOUT ROWID", "AUTOINCREMENT"] {
+            let has1 = table1.options.iter().any(|o| o == option);
+            let has2 = table2.options.iter().any(|o| o == option);
+            if has1 != has2 {
+                let yes_no = |has: bool| if has { "yes" } else { "no" };
+                option_changes.push(format!("{}: {} -> {}", option, yes_no(has1), yes_no(has2)));
+            }
+        }
+        if table1.virtual_module != table2.virtual_module {
+            option_changes.push(format!(
+                "virtual table: {} -> {}",
+                table1.virtual_module.as_deref().unwrap_or("no"),
+                table2.virtual_module.as_deref().unwrap_or("no")
+            ));
+        }
+
         TableDiff {
             table_name: table1.name.clone(),
             added_columns,
             removed_columns,
             modified_columns,
+            column_order_changed,
+            option_changes,
+        }
+    }
+}
+
+/// Build a `file:` URI for `path` that enforces the access mode at the SQLite level
+pub fn sqlite_uri(path: &str, access_mode: AccessMode) -> String {
+    let mut uri_path = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '%' => uri_path.push_str("%25"),
+            '?' => uri_path.push_str("%3f"),
+            '#' => uri_path.push_str("%23"),
+            '\\' if cfg!(windows) => uri_path.push('/'),
+            _ => uri_path.push(c),
+        }
+    }
+    // Windows drive paths need a leading slash (file:///C:/...)
+    if cfg!(windows) && uri_path.as_bytes().get(1) == Some(&b':') {
+        uri_path.insert(0, '/');
+    }
+
+    match access_mode {
+        AccessMode::ReadOnly => format!("file:{}?mode=ro", uri_path),
+        AccessMode::Immutable => format!("file:{}?mode=ro&immutable=1", uri_path),
+        AccessMode::ReadWrite => format!("file:{}?mode=rw", uri_path),
+    }
+}
+
+/// Open a connection to `path`, unlocking it first when it is a SQLCipher database.
+/// Read-only connections can never write, so they are safe for browsing and comparison.
+fn open_connection(
+    path: &str,
+    db_type: DatabaseType,
+    password: &str,
+    settings: Option<&serde_json::Value>,
+    access_mode: AccessMode,
+) -> anyhow::Result<Connection> {
+    let conn = match access_mode {
+        AccessMode::ReadWrite => Connection::open(path),
+        AccessMode::ReadOnly | AccessMode::Immutable => Connection::open_with_flags(
+            sqlite_uri(path, access_mode),
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        ),
+    }
+    .with_context(|| format!("Failed to open database file: {}", path))?;
+
+    if db_type == DatabaseType::SQLCipher {
+        apply_cipher_settings(&conn, password, settings)?;
+    }
+
+    Ok(conn)
+}
+
+/// Set the key and cipher parameters on a freshly opened SQLCipher connection
+fn apply_cipher_settings(
+    conn: &Connection,
+    password: &str,
+    settings: Option<&serde_json::Value>,
+) -> anyhow::Result<()> {
+    // Set password
+    conn.pragma_update(None, "key", password)
+        .with_context(|| "Failed to set encryption key")?;
+
+    apply_cipher_params(conn, CipherPragmas::Connection, settings)
+}
+
+/// Serialises changes to SQLCipher's process-wide cipher defaults
+static CIPHER_DEFAULTS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Which set of SQLCipher pragmas to set: the connection's own or the defaults used for ATTACH
+#[derive(Clone, Copy)]
+enum CipherPragmas {
+    Connection,
+    Defaults,
+}
+
+impl CipherPragmas {
+    /// Pragma names for page size, KDF iterations, HMAC algorithm and KDF algorithm
+    fn names(self) -> [&'static str; 4] {
+        match self {
+            CipherPragmas::Connection => [
+                "cipher_page_size",
+                "kdf_iter",
+                "cipher_hmac_algorithm",
+                "cipher_kdf_algorithm",
+            ],
+            CipherPragmas::Defaults => [
+                "cipher_default_page_size",
+                "cipher_default_kdf_iter",
+                "cipher_default_hmac_algorithm",
+                "cipher_default_kdf_algorithm",
+            ],
+        }
+    }
+}
+
+fn read_cipher_defaults(conn: &Connection) -> anyhow::Result<Vec<String>> {
+    CipherPragmas::Defaults
+        .names()
+        .iter()
+        .map(|name| {
+            conn.query_row(&format!("PRAGMA {}", name), [], |row| {
+                row.get::<_, rusqlite::types::Value>(0)
+            })
+            .map(|value| match value {
+                Value::Integer(i) => i.to_string(),
+                Value::Text(s) => s,
+                other => format!("{:?}", other),
+            })
+            .with_context(|| format!("Failed to read {}", name))
+        })
+        .collect()
+}
+
+fn restore_cipher_defaults(conn: &Connection, previous: &[String]) {
+    for (name, value) in CipherPragmas::Defaults.names().iter().zip(previous) {
+        if let Err(e) = conn.pragma_update(None, name, value) {
+            println!("⚠️ Failed to restore {}: {}", name, e);
+        }
+    }
+}
+
+/// Apply cipher parameters from the user's settings, falling back to SQLCipher 4 defaults
+fn apply_cipher_params(
+    conn: &Connection,
+    pragmas: CipherPragmas,
+    settings: Option<&serde_json::Value>,
+) -> anyhow::Result<()> {
+    // Apply settings from user or use defaults (SQLCipher 4 defaults with SHA512)
+    let setting = |name: &str, default: &'static str| -> String {
+        settings
+            .and_then(|s| s[name].as_str())
+            .unwrap_or(default)
+            .to_string()
+    };
+    let page_size = setting("page_size", "4096");
+    let kdf_iter = setting("kdf_iterations", "256000");
+    let hmac_algo = setting("hmac_algorithm", "HMAC_SHA512");
+    let kdf_algo = setting("kdf_algorithm", "PBKDF2_HMAC_SHA512");
+
+    println!(
+        "📝 Applying SQLCipher settings: page_size={}, kdf_iter={}",
+        page_size, kdf_iter
+    );
+
+    let [page_size_pragma, kdf_iter_pragma, hmac_pragma, kdf_algo_pragma] = pragmas.names();
+
+    conn.pragma_update(None, page_size_pragma, &page_size)
+        .with_context(|| "Failed to set page size")?;
+
+    conn.pragma_update(None, kdf_iter_pragma, &kdf_iter)
+        .with_context(|| "Failed to set KDF iterations")?;
+
+    conn.pragma_update(None, hmac_pragma, &hmac_algo)
+        .with_context(|| "Failed to set HMAC algorithm")?;
+
+    conn.pragma_update(None, kdf_algo_pragma, &kdf_algo)
+        .with_context(|| "Failed to set KDF algorithm")?;
+
+    Ok(())
+}
+
+/// Options of a table (`STRICT`, `WITHOUT ROWID`, `AUTOINCREMENT`) and the module of a
+/// virtual table, read from its `CREATE` statement
+pub(crate) fn table_options(create_sql: &str) -> (Vec<String>, Option<String>) {
+    let sql = canonical_sql(create_sql);
+    if let Some(rest) = sql.strip_prefix("create virtual table ") {
+        let module = rest.split_once(" using ").map(|(_, using)| {
+            using
+                .split(|c: char| c == '(' || c.is_whitespace())
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        });
+        return (Vec::new(), module);
+    }
+
+    let mut options = Vec::new();
+    // Table options follow the closing bracket of the column list
+    let tail = sql.rfind(')').map(|i| &sql[i + 1..]).unwrap_or_default();
+    for option in tail.split(',').map(str::trim) {
+        match option {
+            "strict" => options.push("STRICT".to_string()),
+            "without rowid" => options.push("WITHOUT ROWID".to_string()),
+            _ => {}
+        }
+    }
+    if has_autoincrement(&sql_tokens(create_sql)) {
+        options.push("AUTOINCREMENT".to_string());
+    }
+    (options, None)
+}
+
+/// The CHECK constraints (`check (...)`), column collations (`<column> collate <name>`)
+/// and generated column expressions (`<column> as (...)`) of a `CREATE TABLE`, sorted.
+/// Expressions are token by token, so formatting doesn't matter.
+fn column_clauses(create_sql: &str) -> Vec<String> {
+    let tokens = sql_tokens(create_sql);
+    // The column list is the first bracket, unless the table is `CREATE TABLE ... AS`
+    let Some(open) = tokens.iter().position(|t| t == "(" || t == "as") else {
+        return Vec::new();
+    };
+    if tokens[open] == "as" {
+        return Vec::new();
+    }
+
+    // Split the column list into definitions at top-level commas
+    let mut definitions: Vec<&[String]> = Vec::new();
+    let (mut depth, mut start) = (0, open + 1);
+    for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+        match token.as_str() {
+            "(" => depth += 1,
+            ")" if depth == 0 => {
+                definitions.push(&tokens[start..i]);
+                break;
+            }
+            ")" => depth -= 1,
+            "," if depth == 0 => {
+                definitions.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
         }
     }
+
+    // The bracketed expression starting at `open`, brackets included
+    let bracketed = |definition: &[String], open: usize| -> String {
+        let mut depth = 0;
+        let mut end = definition.len();
+        for (i, token) in definition.iter().enumerate().skip(open) {
+            match token.as_str() {
+                "(" => depth += 1,
+                ")" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = i + 1;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        definition[open..end].join(" ")
+    };
+
+    let mut clauses = Vec::new();
+    for definition in definitions {
+        let Some(first) = definition.first() else {
+            continue;
+        };
+        let column = match first.as_str() {
+            "constraint" | "primary" | "unique" | "check" | "foreign" => None,
+            name => Some(name.trim_matches(['"', '`', '[', ']']).to_lowercase()),
+        };
+        for (i, token) in definition.iter().enumerate() {
+            let next = definition.get(i + 1).map(String::as_str);
+            match (token.as_str(), &column) {
+                ("check", _) if next == Some("(") => {
+                    clauses.push(format!("check {}", bracketed(definition, i + 1)));
+                }
+                ("collate", Some(column)) => {
+                    let collation = next.unwrap_or_default().trim_matches(['"', '\'', '`']);
+                    clauses.push(format!("{} collate {}", column, collation.to_lowercase()));
+                }
+                ("as", Some(column)) if i > 0 && next == Some("(") => {
+                    clauses.push(format!("{} as {}", column, bracketed(definition, i + 1)));
+                }
+                _ => {}
+            }
+        }
+    }
+    clauses.sort();
+    clauses
+}
+
+/// Whether a column is declared `PRIMARY KEY [ASC|DESC] [ON CONFLICT ...] AUTOINCREMENT`
+fn has_autoincrement(tokens: &[String]) -> bool {
+    let word = |i: usize| tokens.get(i).map(String::as_str).unwrap_or_default();
+    (0..tokens.len()).any(|i| {
+        if word(i) != "primary" || word(i + 1) != "key" {
+            return false;
+        }
+        let mut next = i + 2;
+        if matches!(word(next), "asc" | "desc") {
+            next += 1;
+        }
+        if word(next) == "on" && word(next + 1) == "conflict" {
+            next += 3;
+        }
+        word(next) == "autoincrement"
+    })
+}
+
+/// The tokens of a statement: lowercased words, quoted names and strings (kept as
+/// written, quotes included) and single punctuation characters. Comments are dropped.
+fn sql_tokens(sql: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, n)| n);
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '-' && next == Some('-') {
+            chars.find(|&(_, c)| c == '\n');
+            continue;
+        }
+        if c == '/' && next == Some('*') {
+            chars.next();
+            let mut star = false;
+            for (_, c) in chars.by_ref() {
+                if star && c == '/' {
+                    break;
+                }
+                star = c == '*';
+            }
+            continue;
+        }
+        let close = match c {
+            '\'' | '"' | '`' => Some(c),
+            '[' => Some(']'),
+            _ => None,
+        };
+        let end = if let Some(close) = close {
+            // A doubled quote inside is part of the text
+            loop {
+                match chars.next() {
+                    Some((i, c)) if c == close => {
+                        if close != ']' && chars.peek().map(|&(_, n)| n) == Some(close) {
+                            chars.next();
+                            continue;
+                        }
+                        break i + c.len_utf8();
+                    }
+                    Some(_) => {}
+                    None => break sql.len(),
+                }
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_' || c == '$') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(sql[start..end].to_lowercase());
+            continue;
+        } else {
+            start + c.len_utf8()
+        };
+        tokens.push(sql[start..end].to_string());
+    }
+    tokens
+}
+
+/// `sql` with runs of whitespace collapsed (and dropped next to brackets and commas) and
+/// everything outside quotes lowercased
+fn canonical_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut quote: Option<char> = None;
+    let mut pending_space = false;
+    for c in sql.trim().chars() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if pending_space && !matches!(c, '(' | ')' | ',') && !out.ends_with(['(', ',']) {
+            out.push(' ');
+        }
+        pending_space = false;
+        quote = match c {
+            '\'' | '"' | '`' => Some(c),
+            '[' => Some(']'),
+            _ => None,
+        };
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+/// Prepare a single user-supplied, read-only statement
+pub(crate) fn prepare_read_only<'conn>(
+    conn: &'conn Connection,
+    sql: &str,
+) -> anyhow::Result<rusqlite::Statement<'conn>> {
+    // One statement only; `prepare` would silently ignore anything after the first
+    let mut batch = rusqlite::Batch::new(conn, sql);
+    let stmt = batch.next()?.context("No SQL statement to run")?;
+    if batch.next()?.is_some() {
+        anyhow::bail!("Only a single statement can be run at a time");
+    }
+    if !stmt.readonly() || changes_connection_state(&stmt.expanded_sql().unwrap_or_default()) {
+        anyhow::bail!("Only read-only statements (SELECT, read-only PRAGMA, ...) can be run here");
+    }
+    Ok(stmt)
+}
+
+/// Pragmas that take an argument but only read
+const QUERY_PRAGMAS: &[&str] = &[
+    "foreign_key_check",
+    "foreign_key_list",
+    "index_info",
+    "index_list",
+    "index_xinfo",
+    "integrity_check",
+    "quick_check",
+    "table_info",
+    "table_list",
+    "table_xinfo",
+];
+
+/// Statements SQLite counts as read-only that still change the connection: transaction
+/// control, ATTACH/DETACH and pragmas that set something. They would stay in effect on
+/// the pooled reader.
+fn changes_connection_state(sql: &str) -> bool {
+    let sql = skip_comments(sql);
+    let keyword: String = sql
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match keyword.as_str() {
+        "begin" | "commit" | "end" | "rollback" | "savepoint" | "release" | "attach" | "detach" => {
+            true
+        }
+        "pragma" => {
+            let rest = sql[keyword.len()..].trim_start();
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '"')))
+                .unwrap_or(rest.len());
+            let name = rest[..end]
+                .rsplit('.')
+                .next()
+                .unwrap_or_default()
+                .trim_matches('"')
+                .to_ascii_lowercase();
+            let argument = rest[end..].trim_start();
+            argument.starts_with('=')
+                || (argument.starts_with('(') && !QUERY_PRAGMAS.contains(&name.as_str()))
+        }
+        _ => false,
+    }
+}
+
+fn has_attached_databases(conn: &Connection) -> bool {
+    conn.query_row(
+        "SELECT count(*) FROM pragma_database_list WHERE name NOT IN ('main', 'temp')",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map_or(true, |count| count > 0)
+}
+
+fn skip_comments(mut sql: &str) -> &str {
+    loop {
+        sql = sql.trim_start();
+        if let Some(rest) = sql.strip_prefix("--") {
+            sql = rest.split_once('\n').map(|(_, r)| r).unwrap_or_default();
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            sql = rest.split_once("*/").map(|(_, r)| r).unwrap_or_default();
+        } else {
+            return sql;
+        }
+    }
+}
+
+fn run_query(
+    conn: &Connection,
+    sql: &str,
+    max_rows: usize,
+    explain: bool,
+) -> anyhow::Result<QueryResult> {
+    let mut stmt = prepare_read_only(conn, sql)?;
+
+    let columns: Vec<QueryColumn> = stmt
+        .columns()
+        .iter()
+        .map(|c| QueryColumn {
+            name: c.name().to_string(),
+            declared_type: c.decl_type().map(|t| t.to_string()),
+        })
+        .collect();
+    let column_count = columns.len();
+
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let mut result_rows = stmt.query([])?;
+    while let Some(row) = result_rows.next()? {
+        if rows.len() == max_rows {
+            truncated = true;
+            break;
+        }
+        let mut row_data = Vec::with_capacity(column_count);
+        for i in 0..column_count {
+            row_data.push(typed_value_to_json(row.get(i)?));
+        }
+        rows.push(row_data);
+    }
+    drop(result_rows);
+
+    let query_plan = if explain {
+        let mut plan_stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql))?;
+        let steps = plan_stmt
+            .query_map([], |row| {
+                Ok(QueryPlanStep {
+                    id: row.get(0)?,
+                    parent: row.get(1)?,
+                    detail: row.get(3)?,
+                })
+            })?
+            .collect::<RusqliteResult<Vec<_>>>()?;
+        Some(steps)
+    } else {
+        None
+    };
+
+    Ok(QueryResult {
+        columns,
+        rows,
+        truncated,
+        elapsed_ms: 0,
+        query_plan,
+    })
+}
+
+/// Run `f`, interrupting whatever `conn` is executing (`sqlite3_interrupt`) once
+/// `timeout` has passed. The watchdog is joined before returning so it can never hit the
+/// next user of the connection.
+fn interrupt_after<T>(
+    conn: &Connection,
+    timeout: Duration,
+    f: impl FnOnce() -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let interrupt = conn.get_interrupt_handle();
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    let watchdog = std::thread::spawn(move || {
+        if let Err(mpsc::RecvTimeoutError::Timeout) = done_rx.recv_timeout(timeout) {
+            interrupt.interrupt();
+        }
+    });
+
+    let result = f();
+
+    let _ = done_tx.send(());
+    let _ = watchdog.join();
+    result
+}
+
+fn is_interrupted(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<rusqlite::Error>(),
+        Some(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::OperationInterrupted
+    )
+}
+
+/// Convert a cell to JSON keeping its type; blobs are sent as base64
+pub(crate) fn typed_value_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Blob(b) => serde_json::json!({ "blob": BASE64_STANDARD.encode(b) }),
+        other => value_to_json(other),
+    }
+}
+
+/// Inverse of `typed_value_to_json`
+pub(crate) fn json_to_value(value: &serde_json::Value) -> anyhow::Result<Value> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        serde_json::Value::Object(object) => match object.get("blob").and_then(|b| b.as_str()) {
+            Some(blob) => Value::Blob(
+                BASE64_STANDARD
+                    .decode(blob)
+                    .context("Invalid base64 in blob value")?,
+            ),
+            None => anyhow::bail!("Unsupported value: {}", value),
+        },
+        serde_json::Value::Array(_) => anyhow::bail!("Unsupported value: {}", value),
+    })
+}
+
+/// Lowercase hex of hashes and blob literals
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Convert a cell to JSON for display (blobs are summarised, not sent)
+pub(crate) fn value_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => serde_json::Value::Number(i.into()),
+        Value::Real(f) => {
+            serde_json::Value::Number(serde_json::Number::from_f64(f).unwrap_or(0.into()))
+        }
+        Value::Text(s) => serde_json::Value::String(s),
+        Value::Blob(b) => serde_json::Value::String(format!("<BLOB {} bytes>", b.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{column_clauses, table_options};
+
+    fn options(sql: &str) -> Vec<String> {
+        table_options(sql).0
+    }
+
+    #[test]
+    fn table_options_reads_trailing_options() {
+        assert_eq!(
+            options("CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT) STRICT, WITHOUT ROWID"),
+            ["STRICT", "WITHOUT ROWID"]
+        );
+        assert!(options("CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT)").is_empty());
+        assert_eq!(
+            table_options("CREATE VIRTUAL TABLE docs USING fts5(body)"),
+            (Vec::new(), Some("fts5".to_string()))
+        );
+    }
+
+    #[test]
+    fn table_options_finds_autoincrement_keyword() {
+        for sql in [
+            "CREATE TABLE t(id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT)",
+            "create table t(id integer primary key desc on conflict replace autoincrement)",
+            "CREATE TABLE t(\n  id INTEGER PRIMARY KEY -- surrogate\n  AUTOINCREMENT\n)",
+        ] {
+            assert_eq!(options(sql), ["AUTOINCREMENT"], "{}", sql);
+        }
+    }
+
+    #[test]
+    fn table_options_ignores_autoincrement_elsewhere() {
+        for sql in [
+            "CREATE TABLE t(id INTEGER PRIMARY KEY, autoincrement_note TEXT)",
+            "CREATE TABLE t(id INTEGER PRIMARY KEY, note TEXT DEFAULT 'autoincrement')",
+            "CREATE TABLE t(id INTEGER PRIMARY KEY, \"autoincrement\" TEXT)",
+            "CREATE TABLE t(id INTEGER PRIMARY KEY /* not autoincrement */, name TEXT)",
+            "CREATE TABLE t(a INT, b INT, PRIMARY KEY(a, b))",
+        ] {
+            assert!(options(sql).is_empty(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn column_clauses_cover_checks_collations_and_generated_columns() {
+        let clauses = column_clauses(
+            "CREATE TABLE t(id INTEGER PRIMARY KEY, \"Name\" TEXT COLLATE NOCASE CHECK(length(Name)>0), \
+             total REAL GENERATED ALWAYS AS (price*qty) STORED, price REAL, qty INT, \
+             CONSTRAINT positive CHECK (price >= 0))",
+        );
+        assert_eq!(
+            clauses,
+            [
+                "check ( length ( name ) > 0 )",
+                "check ( price > = 0 )",
+                "name collate nocase",
+                "total as ( price * qty )",
+            ]
+        );
+        assert_eq!(
+            column_clauses(
+                "create table t(id integer primary key,\"Name\" text collate nocase check (length( name ) > 0),\
+                 total real as(price * qty) stored,price real,qty int,constraint positive check(price>=0))"
+            ),
+            clauses
+        );
+        assert!(column_clauses("CREATE TABLE t AS SELECT 1 AS x").is_empty());
+        assert_ne!(
+            column_clauses("CREATE TABLE t(a TEXT CHECK(a <> 'x'))"),
+            column_clauses("CREATE TABLE t(a TEXT CHECK(a <> 'X'))")
+        );
+    }
 }
@@ -0,0 +1,237 @@
+// src-tauri/src/dump.rs
+use crate::browse::quote_identifier;
+use crate::database::{hex, DatabaseManager};
+use crate::models::DumpResult;
+use anyhow::Context;
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+/// Schema objects of the main database, in an order they can be recreated in:
+/// tables, then indexes, views (dependencies first) and triggers
+#[derive(Debug, Default)]
+pub struct SchemaObjects {
+    /// `(name, sql)` of user tables; shadow tables of virtual tables are left out
+    pub tables: Vec<(String, String)>,
+    pub indexes: Vec<String>,
+    pub views: Vec<String>,
+    pub triggers: Vec<String>,
+}
+
+pub fn read_schema_objects(conn: &Connection) -> rusqlite::Result<SchemaObjects> {
+    let shadow_tables = shadow_tables(conn)?;
+    let mut objects = SchemaObjects::default();
+    let mut views = Vec::new();
+
+    let mut stmt = conn.prepare(
+        "SELECT type, name, sql FROM sqlite_master WHERE sql IS NOT NULL ORDER BY rowid",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let kind: String = row.get(0)?;
+        let name: String = row.get(1)?;
+        let sql: String = row.get(2)?;
+        match kind.as_str() {
+            "table" if !name.starts_with("sqlite_") && !shadow_tables.contains(&name) => {
+                objects.tables.push((name, sql))
+            }
+            "index" => objects.indexes.push(sql),
+            "view" => views.push((name, sql)),
+            "trigger" => objects.triggers.push(sql),
+            _ => {}
+        }
+    }
+
+    objects.views = order_views(views);
+    Ok(objects)
+}
+
+/// Shadow tables are created by their virtual table and must not be created twice
+pub(crate) fn shadow_tables(conn: &Connection) -> rusqlite::Result<HashSet<String>> {
+    conn.prepare("SELECT name FROM pragma_table_list WHERE schema = 'main' AND type = 'shadow'")?
+        .query_map([], |row| row.get(0))?
+        .collect()
+}
+
+/// Views may select from other views; emit each one after the views it mentions
+pub(crate) fn order_views(mut pending: Vec<(String, String)>) -> Vec<String> {
+    let mut ordered = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|(name, sql)| {
+                !pending
+                    .iter()
+                    .any(|(other, _)| other != name && mentions(sql, other))
+            })
+            .unwrap_or(0);
+        ordered.push(pending.remove(ready).1);
+    }
+    ordered
+}
+
+fn mentions(sql: &str, name: &str) -> bool {
+    let sql = sql.to_lowercase();
+    let name = name.to_lowercase();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    sql.match_indices(&name).any(|(i, _)| {
+        !sql[..i].chars().next_back().is_some_and(is_word)
+            && !sql[i + name.len()..].chars().next().is_some_and(is_word)
+    })
+}
+
+/// Write schema and data of a connected database (plain or SQLCipher) as a SQL script that
+/// replays into an empty database, like the sqlite3 shell's `.dump`. The script is written
+/// to `<output>.part` and only replaces `output_path` once complete.
+pub fn dump(
+    manager: &DatabaseManager,
+    db_path: &str,
+    output_path: &str,
+    include_data: bool,
+    max_rows_per_table: Option<u64>,
+) -> anyhow::Result<DumpResult> {
+    let part_path = format!("{}.part", output_path);
+
+    match write_dump(
+        manager,
+        db_path,
+        &part_path,
+        include_data,
+        max_rows_per_table.unwrap_or(u64::MAX),
+    ) {
+        Ok((tables, rows_written)) => {
+            fs::rename(&part_path, output_path)
+                .with_context(|| format!("Failed to write {}", output_path))?;
+            let file_size = fs::metadata(output_path).map(|m| m.len()).unwrap_or(0);
+
+            println!(
+                "🗄️ Dumped {} tables ({} rows) from {} to {}",
+                tables, rows_written, db_path, output_path
+            );
+            Ok(DumpResult {
+                file_path: output_path.to_string(),
+                tables,
+                rows_written,
+                file_size,
+            })
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&part_path);
+            Err(e)
+        }
+    }
+}
+
+fn write_dump(
+    manager: &DatabaseManager,
+    db_path: &str,
+    path: &str,
+    include_data: bool,
+    max_rows: u64,
+) -> anyhow::Result<(usize, u64)> {
+    let conn = manager.reader(db_path)?;
+    // One read transaction so schema and data come from the same snapshot
+    let _snapshot = conn.unchecked_transaction()?;
+    let objects = read_schema_objects(&conn)?;
+
+    let mut out =
+        BufWriter::new(File::create(path).with_context(|| format!("Failed to create {}", path))?);
+    writeln!(out, "PRAGMA foreign_keys=OFF;")?;
+    writeln!(out, "BEGIN TRANSACTION;")?;
+
+    let mut rows_written = 0u64;
+    for (name, sql) in &objects.tables {
+        writeln!(out, "{};", sql)?;
+        if include_data {
+            rows_written += write_rows(&conn, &mut out, name, max_rows)
+                .with_context(|| format!("Failed to dump table {}", name))?;
+        }
+    }
+
+    // AUTOINCREMENT counters; the table itself is created along with the first such table
+    let has_sequence: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'sqlite_sequence'",
+        [],
+        |row| row.get(0),
+    )?;
+    if include_data && has_sequence {
+        writeln!(out, "DELETE FROM sqlite_sequence;")?;
+        rows_written += write_rows(&conn, &mut out, "sqlite_sequence", max_rows)?;
+    }
+
+    for sql in objects
+        .indexes
+        .iter()
+        .chain(&objects.views)
+        .chain(&objects.triggers)
+    {
+        writeln!(out, "{};", sql)?;
+    }
+
+    writeln!(out, "COMMIT;")?;
+    out.flush()?;
+    Ok((objects.tables.len(), rows_written))
+}
+
+fn write_rows(
+    conn: &Connection,
+    out: &mut impl Write,
+    table: &str,
+    max_rows: u64,
+) -> anyhow::Result<u64> {
+    // Generated and hidden columns can't be inserted into
+    let columns: Vec<(String, i64)> = conn
+        .prepare("SELECT name, hidden FROM pragma_table_xinfo(?1)")?
+        .query_map([table], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    let insertable: Vec<String> = columns
+        .iter()
+        .filter(|(_, hidden)| *hidden == 0)
+        .map(|(name, _)| quote_identifier(name))
+        .collect();
+
+    let quoted_table = quote_identifier(table);
+    let target = if insertable.len() == columns.len() {
+        quoted_table.clone()
+    } else {
+        format!("{}({})", quoted_table, insertable.join(","))
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM {}",
+        insertable.join(", "),
+        quoted_table
+    ))?;
+    let mut rows = stmt.query([])?;
+    let mut written = 0u64;
+    while written < max_rows {
+        let Some(row) = rows.next()? else { break };
+        let literals = (0..insertable.len())
+            .map(|i| row.get::<_, Value>(i).map(|v| sql_literal(&v)))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        writeln!(
+            out,
+            "INSERT INTO {} VALUES({});",
+            target,
+            literals.join(",")
+        )?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// SQL literal for a value, as written by the dump
+pub(crate) fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Integer(i) => i.to_string(),
+        // `{:?}` keeps the shortest form that parses back to the same f64
+        Value::Real(f) if f.is_finite() => format!("{:?}", f),
+        Value::Real(f) if *f > 0.0 => "1e999".to_string(),
+        Value::Real(_) => "-1e999".to_string(),
+        Value::Text(s) => format!("'{}'", s.replace('\'', "''")),
+        Value::Blob(bytes) => format!("X'{}'", hex(bytes)),
+    }
+}
@@ -0,0 +1,506 @@
+// src-tauri/src/editing.rs
+use crate::browse::quote_identifier;
+use crate::database::{json_to_value, typed_value_to_json, DatabaseManager};
+use crate::models::{AccessMode, ColumnInfo, RowEdit, RowEditKind};
+use anyhow::{bail, Context};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::Utc;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, OptionalExtension, TransactionBehavior};
+use serde_json::Map;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type Row = Map<String, serde_json::Value>;
+
+const ROWID_ALIASES: [&str; 3] = ["rowid", "_rowid_", "oid"];
+
+/// Row edits made from the data grid during this session, per database, newest last.
+/// Every edit runs in its own transaction on a fresh writable connection.
+#[derive(Clone, Default)]
+pub struct EditLog {
+    inner: Arc<Mutex<EditLogInner>>,
+}
+
+#[derive(Default)]
+struct EditLogInner {
+    edits: HashMap<String, Vec<RowEdit>>,
+    next_id: u64,
+}
+
+impl EditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn history(&self, db_path: &str) -> Vec<RowEdit> {
+        let inner = self.inner.lock().unwrap();
+        inner.edits.get(db_path).cloned().unwrap_or_default()
+    }
+
+    /// Update some columns of one row. `expected` holds the values as they were read;
+    /// the edit is rejected if any of them changed in the meantime.
+    pub fn update_row(
+        &self,
+        manager: &DatabaseManager,
+        db_path: &str,
+        table_name: &str,
+        key: &Row,
+        changes: &Row,
+        expected: Option<&Row>,
+    ) -> anyhow::Result<RowEdit> {
+        if changes.is_empty() {
+            bail!("Nothing to update");
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let mut conn = open_for_edit(manager, db_path)?;
+        let table = EditTable::load(manager, &conn, table_name)?;
+
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let before = table.read_row(&tx, key)?.context("Row not found")?;
+        if let Some(expected) = expected {
+            check_unchanged(&before, expected)?;
+        }
+
+        let mut assignments = Vec::new();
+        let mut params = Vec::new();
+        for (column, value) in changes {
+            let column = table.column(column)?;
+            assignments.push(format!("{} = ?", quote_identifier(&column.name)));
+            params.push(json_to_value(value)?);
+        }
+        let (condition, key_params) = table.key_condition(key)?;
+        params.extend(key_params);
+
+        let updated = tx.execute(
+            &format!(
+                "UPDATE {} SET {} WHERE {}",
+                table.quoted,
+                assignments.join(", "),
+                condition
+            ),
+            params_from_iter(&params),
+        )?;
+        if updated != 1 {
+            bail!("Expected to update 1 row, updated {}", updated);
+        }
+
+        // Changing primary key columns moves the row
+        let new_key = table.key_after_update(key, changes);
+        let after = table.read_row(&tx, &new_key)?;
+        tx.commit()?;
+
+        Ok(inner.record(
+            db_path,
+            table_name,
+            RowEditKind::Update,
+            new_key,
+            Some(before),
+            after,
+        ))
+    }
+
+    pub fn insert_row(
+        &self,
+        manager: &DatabaseManager,
+        db_path: &str,
+        table_name: &str,
+        values: &Row,
+    ) -> anyhow::Result<RowEdit> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut conn = open_for_edit(manager, db_path)?;
+        let table = EditTable::load(manager, &conn, table_name)?;
+
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let key = table.insert(&tx, values, None)?;
+        let after = table.read_row(&tx, &key)?;
+        tx.commit()?;
+
+        Ok(inner.record(db_path, table_name, RowEditKind::Insert, key, None, after))
+    }
+
+    pub fn delete_row(
+        &self,
+        manager: &DatabaseManager,
+        db_path: &str,
+        table_name: &str,
+        key: &Row,
+        expected: Option<&Row>,
+    ) -> anyhow::Result<RowEdit> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut conn = open_for_edit(manager, db_path)?;
+        let table = EditTable::load(manager, &conn, table_name)?;
+
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let before = table.read_row(&tx, key)?.context("Row not found")?;
+        if let Some(expected) = expected {
+            check_unchanged(&before, expected)?;
+        }
+        table.delete(&tx, key)?;
+        tx.commit()?;
+
+        Ok(inner.record(
+            db_path,
+            table_name,
+            RowEditKind::Delete,
+            key.clone(),
+            Some(before),
+            None,
+        ))
+    }
+
+    /// Revert the most recent edit on `db_path`. Refused if the row has been changed
+    /// since, so an undo never overwrites someone else's work.
+    pub fn undo(&self, manager: &DatabaseManager, db_path: &str) -> anyhow::Result<RowEdit> {
+        let mut inner = self.inner.lock().unwrap();
+        let edit = inner
+            .edits
+            .get(db_path)
+            .and_then(|edits| edits.last())
+            .cloned()
+            .context("Nothing to undo")?;
+
+        let mut conn = open_for_edit(manager, db_path)?;
+        let table = EditTable::load(manager, &conn, &edit.table_name)?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        match edit.kind {
+            RowEditKind::Insert | RowEditKind::Update => {
+                let current = table
+                    .read_row(&tx, &edit.key)?
+                    .context("Row no longer exists - cannot undo")?;
+                if let Some(after) = &edit.after {
+                    check_unchanged(&current, after)?;
+                }
+
+                if edit.kind == RowEditKind::Insert {
+                    table.delete(&tx, &edit.key)?;
+                } else {
+                    let before = edit
+                        .before
+                        .as_ref()
+                        .context("Edit has no previous values")?;
+                    let mut assignments = Vec::new();
+                    let mut params = Vec::new();
+                    for (column, value) in before {
+                        assignments.push(format!("{} = ?", quote_identifier(column)));
+                        params.push(json_to_value(value)?);
+                    }
+                    let (condition, key_params) = table.key_condition(&edit.key)?;
+                    params.extend(key_params);
+                    tx.execute(
+                        &format!(
+                            "UPDATE {} SET {} WHERE {}",
+                            table.quoted,
+                            assignments.join(", "),
+                            condition
+                        ),
+                        params_from_iter(&params),
+                    )?;
+                }
+            }
+            RowEditKind::Delete => {
+                let before = edit
+                    .before
+                    .as_ref()
+                    .context("Edit has no previous values")?;
+                // Put the row back under its old rowid as well
+                let rowid = table
+                    .rowid_alias
+                    .and_then(|alias| edit.key.get(alias))
+                    .map(json_to_value)
+                    .transpose()?;
+                table.insert(&tx, before, rowid)?;
+            }
+        }
+        tx.commit()?;
+
+        if let Some(edits) = inner.edits.get_mut(db_path) {
+            edits.pop();
+        }
+        println!(
+            "↩️ Undid {:?} on {} in {}",
+            edit.kind, edit.table_name, db_path
+        );
+        Ok(edit)
+    }
+
+    /// Forget the edits of a database (e.g. after it was disconnected)
+    pub fn clear(&self, db_path: &str) {
+        self.inner.lock().unwrap().edits.remove(db_path);
+    }
+}
+
+impl EditLogInner {
+    fn record(
+        &mut self,
+        db_path: &str,
+        table_name: &str,
+        kind: RowEditKind,
+        key: Row,
+        before: Option<Row>,
+        after: Option<Row>,
+    ) -> RowEdit {
+        self.next_id += 1;
+        let edit = RowEdit {
+            id: self.next_id,
+            db_path: db_path.to_string(),
+            table_name: table_name.to_string(),
+            kind,
+            key,
+            before,
+            after,
+            edited_at: Utc::now(),
+        };
+        println!("✏️ {:?} on {} in {}", kind, table_name, db_path);
+        self.edits
+            .entry(db_path.to_string())
+            .or_default()
+            .push(edit.clone());
+        edit
+    }
+}
+
+/// Writable connection for row edits and imports; refused for immutable databases
+pub(crate) fn open_for_edit(
+    manager: &DatabaseManager,
+    db_path: &str,
+) -> anyhow::Result<Connection> {
+    match manager.access_mode(db_path) {
+        None => bail!("Database not connected"),
+        Some(AccessMode::Immutable) => {
+            bail!("Database is opened as immutable - reconnect it read-write to edit rows")
+        }
+        Some(_) => manager.open_writable(db_path),
+    }
+}
+
+/// Column and key information of the table being edited
+struct EditTable {
+    quoted: String,
+    columns: Vec<ColumnInfo>,
+    /// Rowid alias usable as a key, `None` for WITHOUT ROWID tables and views
+    rowid_alias: Option<&'static str>,
+}
+
+impl EditTable {
+    fn load(
+        manager: &DatabaseManager,
+        conn: &Connection,
+        table_name: &str,
+    ) -> anyhow::Result<Self> {
+        let columns = manager.get_table_columns(conn, table_name)?;
+        if columns.is_empty() {
+            bail!("Table '{}' not found", table_name);
+        }
+        let quoted = quote_identifier(table_name);
+
+        let rowid_alias = ROWID_ALIASES
+            .into_iter()
+            .find(|alias| !columns.iter().any(|c| c.name.eq_ignore_ascii_case(alias)))
+            .filter(|alias| {
+                conn.prepare(&format!("SELECT {} FROM {} LIMIT 0", alias, quoted))
+                    .is_ok()
+            });
+
+        Ok(EditTable {
+            quoted,
+            columns,
+            rowid_alias,
+        })
+    }
+
+    fn column(&self, name: &str) -> anyhow::Result<&ColumnInfo> {
+        self.columns
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+            .with_context(|| format!("Unknown column '{}'", name))
+    }
+
+    fn primary_key(&self) -> Vec<&ColumnInfo> {
+        self.columns.iter().filter(|c| c.is_primary_key).collect()
+    }
+
+    /// WHERE condition for a row key: either the rowid alone or every primary key column
+    fn key_condition(&self, key: &Row) -> anyhow::Result<(String, Vec<Value>)> {
+        if key.len() == 1 {
+            let (name, value) = key.iter().next().unwrap();
+            if let Some(alias) = self.rowid_alias.filter(|a| a.eq_ignore_ascii_case(name)) {
+                return Ok((format!("{} = ?", alias), vec![json_to_value(value)?]));
+            }
+        }
+
+        let primary_key = self.primary_key();
+        if primary_key.is_empty() || primary_key.len() != key.len() {
+            bail!("Rows must be identified by their rowid or their full primary key");
+        }
+
+        let mut terms = Vec::new();
+        let mut params = Vec::new();
+        for column in primary_key {
+            let value = key
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&column.name))
+                .map(|(_, value)| value)
+                .with_context(|| format!("Key is missing primary key column '{}'", column.name))?;
+            terms.push(format!("{} IS ?", quote_identifier(&column.name)));
+            params.push(json_to_value(value)?);
+        }
+        Ok((terms.join(" AND "), params))
+    }
+
+    /// The `INTEGER PRIMARY KEY` column, which is another name for the rowid
+    fn rowid_column(&self) -> Option<&ColumnInfo> {
+        self.rowid_alias?;
+        match self.primary_key()[..] {
+            [column] if column.data_type.eq_ignore_ascii_case("INTEGER") => Some(column),
+            _ => None,
+        }
+    }
+
+    /// The key of a row after `changes`. A row keyed by its rowid moves too when its
+    /// `INTEGER PRIMARY KEY` column changes.
+    fn key_after_update(&self, key: &Row, changes: &Row) -> Row {
+        let rowid_column = self.rowid_column();
+        let is_rowid = |name: &str| {
+            self.rowid_alias
+                .is_some_and(|a| a.eq_ignore_ascii_case(name))
+        };
+        let mut new_key = key.clone();
+        for (name, value) in new_key.iter_mut() {
+            let column = match rowid_column {
+                Some(column) if is_rowid(name) => &column.name,
+                _ => name,
+            };
+            if let Some((_, changed)) = changes
+                .iter()
+                .find(|(changed, _)| changed.eq_ignore_ascii_case(column))
+            {
+                *value = changed.clone();
+            }
+        }
+        new_key
+    }
+
+    fn read_row(&self, conn: &Connection, key: &Row) -> anyhow::Result<Option<Row>> {
+        let (condition, params) = self.key_condition(key)?;
+        let select_list: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| quote_identifier(&c.name))
+            .collect();
+
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM {} WHERE {}",
+                select_list.join(", "),
+                self.quoted,
+                condition
+            ),
+            params_from_iter(&params),
+            |row| {
+                let mut values = Row::new();
+                for (i, column) in self.columns.iter().enumerate() {
+                    values.insert(column.name.clone(), typed_value_to_json(row.get(i)?));
+                }
+                Ok(values)
+            },
+        )
+        .optional()
+        .map_err(Into::into)
+    }
+
+    /// Insert a row and return its key (the rowid where there is one)
+    fn insert(&self, conn: &Connection, values: &Row, rowid: Option<Value>) -> anyhow::Result<Row> {
+        let mut columns = Vec::new();
+        let mut params = Vec::new();
+        if let (Some(alias), Some(rowid)) = (self.rowid_alias, rowid) {
+            columns.push(alias.to_string());
+            params.push(rowid);
+        }
+        for (name, value) in values {
+            columns.push(quote_identifier(&self.column(name)?.name));
+            params.push(json_to_value(value)?);
+        }
+
+        let sql = if columns.is_empty() {
+            format!("INSERT INTO {} DEFAULT VALUES", self.quoted)
+        } else {
+            format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                self.quoted,
+                columns.join(", "),
+                vec!["?"; columns.len()].join(", ")
+            )
+        };
+        conn.execute(&sql, params_from_iter(&params))?;
+
+        let mut key = Row::new();
+        match self.rowid_alias {
+            Some(alias) => {
+                key.insert(alias.to_string(), conn.last_insert_rowid().into());
+            }
+            None => {
+                for column in self.primary_key() {
+                    let value = values
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(&column.name))
+                        .map(|(_, value)| value.clone())
+                        .with_context(|| {
+                            format!("Primary key column '{}' is required", column.name)
+                        })?;
+                    key.insert(column.name.clone(), value);
+                }
+            }
+        }
+        Ok(key)
+    }
+
+    fn delete(&self, conn: &Connection, key: &Row) -> anyhow::Result<()> {
+        let (condition, params) = self.key_condition(key)?;
+        let deleted = conn.execute(
+            &format!("DELETE FROM {} WHERE {}", self.quoted, condition),
+            params_from_iter(&params),
+        )?;
+        if deleted != 1 {
+            bail!("Expected to delete 1 row, deleted {}", deleted);
+        }
+        Ok(())
+    }
+}
+
+/// Optimistic concurrency check: every expected value must still be current
+fn check_unchanged(current: &Row, expected: &Row) -> anyhow::Result<()> {
+    for (column, expected_value) in expected {
+        let current_value = current
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(column))
+            .map(|(_, value)| value)
+            .with_context(|| format!("Unknown column '{}'", column))?;
+
+        if !values_match(current_value, expected_value) {
+            bail!(
+                "Row was changed since it was read (column '{}' is now {})",
+                column,
+                current_value
+            );
+        }
+    }
+    Ok(())
+}
+
+fn values_match(current: &serde_json::Value, expected: &serde_json::Value) -> bool {
+    match (current, expected) {
+        // 1 and 1.0 are the same number in SQLite
+        (serde_json::Value::Number(a), serde_json::Value::Number(b)) => a.as_f64() == b.as_f64(),
+        // The grid only shows "<BLOB n bytes>" for blobs, so compare the size
+        (serde_json::Value::Object(blob), serde_json::Value::String(shown)) => blob
+            .get("blob")
+            .and_then(|b| b.as_str())
+            .and_then(|b| BASE64_STANDARD.decode(b).ok())
+            .map(|bytes| *shown == format!("<BLOB {} bytes>", bytes.len()))
+            .unwrap_or(false),
+        _ => current == expected,
+    }
+}
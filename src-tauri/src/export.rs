@@ -0,0 +1,485 @@
+// src-tauri/src/export.rs
+use crate::browse;
+use crate::database::{prepare_read_only, typed_value_to_json, DatabaseManager};
+use crate::models::{ExportFormat, ExportOptions, ExportResult, ExportSource};
+use anyhow::{bail, Context};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use parquet::basic::{LogicalType, Repetition, Type as PhysicalType};
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Rows buffered per Parquet row group; everything else is written row by row
+const PARQUET_ROW_GROUP_SIZE: usize = 50_000;
+
+/// Default CSV text for NULL, as used by PostgreSQL's and MySQL's text formats
+pub const CSV_NULL: &str = "\\N";
+
+/// Export a table or query result to a file. Rows are streamed from a pooled reader
+/// into `<output>.part`, which only replaces `output_path` once the export succeeded.
+pub fn export(
+    manager: &DatabaseManager,
+    db_path: &str,
+    source: &ExportSource,
+    format: ExportFormat,
+    output_path: &str,
+    options: &ExportOptions,
+) -> anyhow::Result<ExportResult> {
+    let part_path = format!("{}.part", output_path);
+
+    match write_export(manager, db_path, source, format, &part_path, options) {
+        Ok((columns, rows_written)) => {
+            fs::rename(&part_path, output_path)
+                .with_context(|| format!("Failed to write {}", output_path))?;
+            let file_size = fs::metadata(output_path).map(|m| m.len()).unwrap_or(0);
+
+            println!(
+                "📤 Exported {} rows from {} to {} ({:?})",
+                rows_written, db_path, output_path, format
+            );
+            Ok(ExportResult {
+                file_path: output_path.to_string(),
+                format,
+                columns,
+                rows_written,
+                file_size,
+            })
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&part_path);
+            Err(e)
+        }
+    }
+}
+
+fn write_export(
+    manager: &DatabaseManager,
+    db_path: &str,
+    source: &ExportSource,
+    format: ExportFormat,
+    path: &str,
+    options: &ExportOptions,
+) -> anyhow::Result<(Vec<String>, u64)> {
+    let conn = manager.reader(db_path)?;
+
+    let (mut stmt, params) = match source {
+        ExportSource::Table { table_name, query } => {
+            // Same column order, filters and sort order as the browse view
+            let columns = manager.get_table_columns(&conn, table_name)?;
+            if columns.is_empty() {
+                bail!("Table '{}' not found", table_name);
+            }
+            let compiled = match query {
+                Some(query) => browse::compile(query, &columns)?,
+                None => browse::CompiledQuery::default(),
+            };
+            let select_list: Vec<String> = columns
+                .iter()
+                .map(|c| browse::quote_identifier(&c.name))
+                .collect();
+            let sql = format!(
+                "SELECT {} FROM {}{}{}",
+                select_list.join(", "),
+                browse::quote_identifier(table_name),
+                compiled.where_clause,
+                compiled.order_by
+            );
+            (conn.prepare(&sql)?, compiled.params)
+        }
+        ExportSource::Query { sql } => (prepare_read_only(&conn, sql)?, Vec::new()),
+    };
+
+    let columns = unique_names(stmt.column_names());
+    let declared_types: Vec<Option<String>> = stmt
+        .columns()
+        .iter()
+        .map(|c| c.decl_type().map(|t| t.to_string()))
+        .collect();
+
+    let out =
+        BufWriter::new(File::create(path).with_context(|| format!("Failed to create {}", path))?);
+    let mut sink: Box<dyn RowSink> = match format {
+        ExportFormat::Csv => Box::new(CsvSink::new(out, &columns, options)?),
+        ExportFormat::JsonLines => Box::new(JsonLinesSink {
+            out,
+            columns: columns.clone(),
+        }),
+        ExportFormat::Parquet => Box::new(ParquetSink::new(out, &columns, declared_types)),
+    };
+
+    let column_count = columns.len();
+    let max_rows = options.max_rows.unwrap_or(u64::MAX);
+    let mut rows_written = 0u64;
+
+    let mut rows = stmt.query(params_from_iter(&params))?;
+    while rows_written < max_rows {
+        let Some(row) = rows.next()? else { break };
+        let values = (0..column_count)
+            .map(|i| row.get::<_, Value>(i))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        sink.write_row(values)?;
+        rows_written += 1;
+    }
+
+    sink.finish()?;
+    Ok((columns, rows_written))
+}
+
+/// Query results may repeat a column name (`SELECT a, a ...`); every format needs unique ones
+fn unique_names(names: Vec<&str>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::with_capacity(names.len());
+    for name in names {
+        let mut candidate = name.to_string();
+        let mut n = 2;
+        while unique.contains(&candidate) {
+            candidate = format!("{}_{}", name, n);
+            n += 1;
+        }
+        unique.push(candidate);
+    }
+    unique
+}
+
+trait RowSink {
+    fn write_row(&mut self, row: Vec<Value>) -> anyhow::Result<()>;
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+/// CSV with a header row. Reals keep their decimal point, blobs are base64.
+struct CsvSink {
+    writer: csv::Writer<BufWriter<File>>,
+    null_value: String,
+}
+
+impl CsvSink {
+    fn new(
+        out: BufWriter<File>,
+        columns: &[String],
+        options: &ExportOptions,
+    ) -> anyhow::Result<Self> {
+        let delimiter = options.delimiter.unwrap_or(',');
+        if !delimiter.is_ascii() {
+            bail!("CSV delimiter must be a single ASCII character");
+        }
+
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter as u8)
+            .from_writer(out);
+        writer.write_record(columns)?;
+
+        Ok(CsvSink {
+            writer,
+            null_value: options
+                .null_value
+                .clone()
+                .unwrap_or_else(|| CSV_NULL.to_string()),
+        })
+    }
+}
+
+impl RowSink for CsvSink {
+    fn write_row(&mut self, row: Vec<Value>) -> anyhow::Result<()> {
+        for value in row {
+            match value {
+                Value::Null => self.writer.write_field(&self.null_value)?,
+                Value::Integer(i) => self.writer.write_field(i.to_string())?,
+                Value::Real(f) => self.writer.write_field(format!("{:?}", f))?,
+                Value::Text(s) => self.writer.write_field(s)?,
+                Value::Blob(b) => self.writer.write_field(BASE64_STANDARD.encode(b))?,
+            }
+        }
+        self.writer.write_record(None::<&[u8]>)?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// One JSON object per line, using the SQL console encoding (blobs as `{ "blob": base64 }`)
+struct JsonLinesSink {
+    out: BufWriter<File>,
+    columns: Vec<String>,
+}
+
+impl RowSink for JsonLinesSink {
+    fn write_row(&mut self, row: Vec<Value>) -> anyhow::Result<()> {
+        // Written by hand so keys keep the column order
+        self.out.write_all(b"{")?;
+        for (i, (column, value)) in self.columns.iter().zip(row).enumerate() {
+            if i > 0 {
+                self.out.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut self.out, column)?;
+            self.out.write_all(b":")?;
+            serde_json::to_writer(&mut self.out, &typed_value_to_json(value))?;
+        }
+        self.out.write_all(b"}\n")?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Parquet column type, picked from the values of the first row group (SQLite columns
+/// aren't strictly typed) and the declared type. Whole numbers only become INT64 in
+/// columns with INTEGER affinity; elsewhere a later REAL could still turn up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParquetKind {
+    Int64,
+    Double,
+    Text,
+    Binary,
+}
+
+struct ParquetSink {
+    out: Option<BufWriter<File>>,
+    writer: Option<SerializedFileWriter<BufWriter<File>>>,
+    columns: Vec<String>,
+    declared_types: Vec<Option<String>>,
+    kinds: Vec<ParquetKind>,
+    buffered: Vec<Vec<Value>>,
+}
+
+impl ParquetSink {
+    fn new(out: BufWriter<File>, columns: &[String], declared_types: Vec<Option<String>>) -> Self {
+        ParquetSink {
+            out: Some(out),
+            writer: None,
+            columns: columns.to_vec(),
+            declared_types,
+            kinds: Vec::new(),
+            buffered: Vec::new(),
+        }
+    }
+
+    fn start(&mut self) -> anyhow::Result<()> {
+        self.kinds = (0..self.columns.len())
+            .map(|i| {
+                infer_kind(
+                    self.buffered.iter().map(|row| &row[i]),
+                    self.declared_types[i].as_deref(),
+                )
+            })
+            .collect();
+
+        let fields = self
+            .columns
+            .iter()
+            .zip(&self.kinds)
+            .map(|(name, kind)| {
+                let (physical_type, logical_type) = match kind {
+                    ParquetKind::Int64 => (PhysicalType::INT64, None),
+                    ParquetKind::Double => (PhysicalType::DOUBLE, None),
+                    ParquetKind::Text => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+                    ParquetKind::Binary => (PhysicalType::BYTE_ARRAY, None),
+                };
+                Type::primitive_type_builder(name, physical_type)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .with_logical_type(logical_type)
+                    .build()
+                    .map(Arc::new)
+            })
+            .collect::<parquet::errors::Result<Vec<_>>>()?;
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?;
+
+        let out = self.out.take().context("Parquet writer already started")?;
+        self.writer = Some(SerializedFileWriter::new(
+            out,
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )?);
+        Ok(())
+    }
+
+    fn flush_row_group(&mut self) -> anyhow::Result<()> {
+        if self.writer.is_none() {
+            self.start()?;
+        }
+        if self.buffered.is_empty() {
+            return Ok(());
+        }
+
+        let rows = std::mem::take(&mut self.buffered);
+        let writer = self.writer.as_mut().unwrap();
+        let mut row_group = writer.next_row_group()?;
+        let mut index = 0;
+
+        while let Some(mut column) = row_group.next_column()? {
+            let (kind, name) = (self.kinds[index], &self.columns[index]);
+            let mut definition_levels = Vec::with_capacity(rows.len());
+
+            match column.untyped() {
+                ColumnWriter::Int64ColumnWriter(w) => {
+                    let mut values = Vec::new();
+                    for row in &rows {
+                        match &row[index] {
+                            Value::Null => definition_levels.push(0),
+                            Value::Integer(i) => {
+                                definition_levels.push(1);
+                                values.push(*i);
+                            }
+                            other => return Err(mixed_types(name, kind, other)),
+                        }
+                    }
+                    w.write_batch(&values, Some(&definition_levels), None)?;
+                }
+                ColumnWriter::DoubleColumnWriter(w) => {
+                    let mut values = Vec::new();
+                    for row in &rows {
+                        match &row[index] {
+                            Value::Null => definition_levels.push(0),
+                            Value::Integer(i) => {
+                                definition_levels.push(1);
+                                values.push(*i as f64);
+                            }
+                            Value::Real(f) => {
+                                definition_levels.push(1);
+                                values.push(*f);
+                            }
+                            other => return Err(mixed_types(name, kind, other)),
+                        }
+                    }
+                    w.write_batch(&values, Some(&definition_levels), None)?;
+                }
+                ColumnWriter::ByteArrayColumnWriter(w) => {
+                    let mut values = Vec::new();
+                    for row in &rows {
+                        let bytes = match &row[index] {
+                            Value::Null => None,
+                            Value::Integer(i) => Some(i.to_string().into_bytes()),
+                            Value::Real(f) => Some(format!("{:?}", f).into_bytes()),
+                            Value::Text(s) => Some(s.clone().into_bytes()),
+                            Value::Blob(b) if kind == ParquetKind::Binary => Some(b.clone()),
+                            other => return Err(mixed_types(name, kind, other)),
+                        };
+                        match bytes {
+                            Some(bytes) => {
+                                definition_levels.push(1);
+                                values.push(ByteArray::from(bytes));
+                            }
+                            None => definition_levels.push(0),
+                        }
+                    }
+                    w.write_batch(&values, Some(&definition_levels), None)?;
+                }
+                _ => bail!("Unexpected Parquet column type for '{}'", name),
+            }
+
+            column.close()?;
+            index += 1;
+        }
+
+        row_group.close()?;
+        Ok(())
+    }
+}
+
+impl RowSink for ParquetSink {
+    fn write_row(&mut self, row: Vec<Value>) -> anyhow::Result<()> {
+        self.buffered.push(row);
+        if self.buffered.len() >= PARQUET_ROW_GROUP_SIZE {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.flush_row_group()?;
+        if let Some(writer) = self.writer.take() {
+            let mut out = writer.into_inner()?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn infer_kind<'a>(
+    values: impl Iterator<Item = &'a Value>,
+    declared_type: Option<&str>,
+) -> ParquetKind {
+    let (mut integer, mut real, mut text, mut blob) = (false, false, false, false);
+    for value in values {
+        match value {
+            Value::Null => {}
+            Value::Integer(_) => integer = true,
+            Value::Real(_) => real = true,
+            Value::Text(_) => text = true,
+            Value::Blob(_) => blob = true,
+        }
+    }
+
+    if blob {
+        ParquetKind::Binary
+    } else if text {
+        ParquetKind::Text
+    } else if real {
+        ParquetKind::Double
+    } else if integer {
+        if has_integer_affinity(declared_type) {
+            ParquetKind::Int64
+        } else {
+            ParquetKind::Double
+        }
+    } else {
+        // All NULL so far: follow SQLite's type affinity rules
+        let declared = declared_type.unwrap_or_default().to_uppercase();
+        if has_integer_affinity(declared_type) {
+            ParquetKind::Int64
+        } else if declared.contains("CHAR")
+            || declared.contains("CLOB")
+            || declared.contains("TEXT")
+        {
+            ParquetKind::Text
+        } else if declared.contains("BLOB") {
+            ParquetKind::Binary
+        } else if declared.contains("REAL")
+            || declared.contains("FLOA")
+            || declared.contains("DOUB")
+        {
+            ParquetKind::Double
+        } else {
+            ParquetKind::Text
+        }
+    }
+}
+
+fn has_integer_affinity(declared_type: Option<&str>) -> bool {
+    declared_type.is_some_and(|t| t.to_uppercase().contains("INT"))
+}
+
+fn mixed_types(column: &str, kind: ParquetKind, value: &Value) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Column '{}' was exported as {:?} but later holds a {:?} value; Parquet can't mix \
+         types in one column - export as JSON Lines instead",
+        column,
+        kind,
+        value.data_type()
+    )
+}
+
+/// Reject output paths in directories that don't exist before any work is done
+pub fn check_output_path(output_path: &str) -> Result<(), String> {
+    let parent = Path::new(output_path).parent();
+    match parent {
+        Some(dir) if !dir.as_os_str().is_empty() && !dir.exists() => {
+            Err(format!("Directory does not exist: {}", dir.display()))
+        }
+        _ => Ok(()),
+    }
+}
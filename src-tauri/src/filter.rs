@@ -0,0 +1,64 @@
+// src-tauri/src/filter.rs
+use crate::models::{ComparisonFilter, TableInfo};
+
+impl ComparisonFilter {
+    pub fn includes_table(&self, table: &str) -> bool {
+        (self.include_tables.is_empty() || self.include_tables.iter().any(|p| glob_match(p, table)))
+            && !self.exclude_tables.iter().any(|p| glob_match(p, table))
+    }
+
+    pub fn ignores_column(&self, table: &str, column: &str) -> bool {
+        self.ignored_columns
+            .iter()
+            .any(|pattern| match pattern.split_once('.') {
+                Some((table_pattern, column_pattern)) => {
+                    glob_match(table_pattern, table) && glob_match(column_pattern, column)
+                }
+                None => glob_match(pattern, column),
+            })
+    }
+
+    /// Drop excluded tables and ignored columns before comparing
+    pub fn apply(&self, tables: Vec<TableInfo>) -> Vec<TableInfo> {
+        tables
+            .into_iter()
+            .filter(|t| self.includes_table(&t.name))
+            .map(|mut t| {
+                let name = t.name.clone();
+                t.columns.retain(|c| !self.ignores_column(&name, &c.name));
+                t
+            })
+            .collect()
+    }
+}
+
+/// Case-insensitive glob with `*` (any run of characters) and `?` (one character)
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    // Backtrack to the last `*` on a mismatch
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
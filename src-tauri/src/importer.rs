@@ -0,0 +1,517 @@
+// src-tauri/src/importer.rs
+use crate::browse::quote_identifier;
+use crate::database::DatabaseManager;
+use crate::editing::open_for_edit;
+use crate::export::CSV_NULL;
+use crate::models::{ColumnInfo, ImportFormat, ImportOptions, ImportResult, RejectedRow};
+use anyhow::{bail, Context};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+const DEFAULT_BATCH_SIZE: usize = 1000;
+/// Records sampled to find JSON Lines fields and to infer column types for a new table
+const SAMPLE_SIZE: usize = 1000;
+/// Rejected rows reported back individually; the rest are only counted
+const MAX_REPORTED_REJECTIONS: usize = 500;
+
+/// A value as read from the file, before it is converted for its target column
+#[derive(Debug, Clone)]
+enum SourceValue {
+    Null,
+    Text(String),
+    Json(serde_json::Value),
+}
+
+struct SourceRecord {
+    line: u64,
+    /// One value per source column, or the reason the record couldn't be read
+    values: Result<Vec<SourceValue>, String>,
+}
+
+/// Column type affinity, following SQLite's rules for declared types
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Affinity {
+    Integer,
+    Real,
+    Numeric,
+    Text,
+    Blob,
+}
+
+impl Affinity {
+    pub(crate) fn of(declared_type: &str) -> Self {
+        let declared = declared_type.to_uppercase();
+        if declared.contains("INT") {
+            Affinity::Integer
+        } else if declared.contains("CHAR")
+            || declared.contains("CLOB")
+            || declared.contains("TEXT")
+        {
+            Affinity::Text
+        } else if declared.contains("BLOB") || declared.is_empty() {
+            Affinity::Blob
+        } else if declared.contains("REAL")
+            || declared.contains("FLOA")
+            || declared.contains("DOUB")
+        {
+            Affinity::Real
+        } else {
+            Affinity::Numeric
+        }
+    }
+}
+
+/// Import a CSV (with header row) or JSON Lines file into `table_name`. Rows are inserted
+/// with one prepared statement, committing every `batch_size` rows; rows that can't be
+/// converted or violate a constraint are skipped and reported.
+pub fn import(
+    manager: &DatabaseManager,
+    db_path: &str,
+    table_name: &str,
+    file_path: &str,
+    format: ImportFormat,
+    options: &ImportOptions,
+) -> anyhow::Result<ImportResult> {
+    let conn = open_for_edit(manager, db_path)?;
+
+    let (mut source_columns, sample) = sample_source(file_path, format, options)?;
+    if source_columns.is_empty() {
+        bail!("No columns found in {}", file_path);
+    }
+
+    // A new table is created in the import's first transaction, so an import that fails
+    // before its first commit doesn't leave an empty table behind (returning early drops
+    // the connection, which rolls back)
+    conn.execute_batch("BEGIN")?;
+    let mut columns = manager.get_table_columns(&conn, table_name)?;
+    let created_table = columns.is_empty();
+    if created_table {
+        if !options.create_table {
+            bail!("Table '{}' not found", table_name);
+        }
+        create_table(&conn, table_name, &source_columns, &sample, options)?;
+        columns = manager.get_table_columns(&conn, table_name)?;
+    }
+
+    // Source column index -> target column
+    let mut mapping: Vec<(usize, &ColumnInfo)> = Vec::new();
+    let mut ignored_columns = Vec::new();
+    for (index, source_column) in source_columns.iter().enumerate() {
+        let target_name = options
+            .column_mapping
+            .get(source_column)
+            .unwrap_or(source_column);
+        match columns
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(target_name))
+        {
+            Some(column) => mapping.push((index, column)),
+            None if options.column_mapping.contains_key(source_column) => {
+                bail!(
+                    "Mapped column '{}' not found in '{}'",
+                    target_name,
+                    table_name
+                )
+            }
+            None => ignored_columns.push(source_column.clone()),
+        }
+    }
+    // Only the sample was searched for JSON Lines fields; ones that first turn up later
+    // are accepted if they name a column of the table, directly or through the mapping
+    if matches!(format, ImportFormat::JsonLines) {
+        let candidates: Vec<(String, String)> = options
+            .column_mapping
+            .iter()
+            .map(|(field, target)| (field.clone(), target.clone()))
+            .chain(columns.iter().map(|c| (c.name.clone(), c.name.clone())))
+            .collect();
+        for (field, target) in candidates {
+            let already_mapped = mapping
+                .iter()
+                .any(|(_, c)| c.name.eq_ignore_ascii_case(&target));
+            if source_columns.contains(&field) || already_mapped {
+                continue;
+            }
+            if let Some(column) = columns
+                .iter()
+                .find(|c| c.name.eq_ignore_ascii_case(&target))
+            {
+                source_columns.push(field);
+                mapping.push((source_columns.len() - 1, column));
+            }
+        }
+    }
+    if mapping.is_empty() {
+        bail!(
+            "None of the file's columns match a column of '{}'",
+            table_name
+        );
+    }
+
+    let target_list: Vec<String> = mapping
+        .iter()
+        .map(|(_, column)| quote_identifier(&column.name))
+        .collect();
+    let mut stmt = conn.prepare(&format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        quote_identifier(table_name),
+        target_list.join(", "),
+        vec!["?"; mapping.len()].join(", ")
+    ))?;
+
+    let mut result = ImportResult {
+        table_name: table_name.to_string(),
+        created_table,
+        columns: mapping.iter().map(|(_, c)| c.name.clone()).collect(),
+        ignored_columns,
+        rows_read: 0,
+        rows_inserted: 0,
+        rows_rejected: 0,
+        rejected: Vec::new(),
+    };
+
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+    let max_rows = options.max_rows.unwrap_or(u64::MAX);
+    let mut in_batch = 0;
+    let mut committed_rows = 0;
+
+    let outcome = (|| -> anyhow::Result<()> {
+        for record in read_source(file_path, format, &source_columns, options)? {
+            if result.rows_read >= max_rows {
+                break;
+            }
+            let record = record?;
+            result.rows_read += 1;
+
+            let inserted = record.values.and_then(|values| {
+                let params = mapping
+                    .iter()
+                    .map(|(index, column)| convert(&values[*index], column))
+                    .collect::<Result<Vec<_>, String>>()?;
+                // A failed INSERT only undoes itself, the batch carries on
+                stmt.execute(params_from_iter(&params))
+                    .map_err(|e| e.to_string())
+            });
+
+            match inserted {
+                Ok(_) => result.rows_inserted += 1,
+                Err(reason) => {
+                    result.rows_rejected += 1;
+                    if result.rejected.len() < MAX_REPORTED_REJECTIONS {
+                        result.rejected.push(RejectedRow {
+                            line: record.line,
+                            reason,
+                        });
+                    }
+                }
+            }
+
+            in_batch += 1;
+            if in_batch == batch_size {
+                conn.execute_batch("COMMIT; BEGIN")?;
+                committed_rows = result.rows_inserted;
+                in_batch = 0;
+            }
+        }
+        Ok(())
+    })();
+
+    match outcome {
+        Ok(()) => conn.execute_batch("COMMIT")?,
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            return Err(e.context(format!(
+                "Import stopped at row {}; {} rows from earlier batches were kept",
+                result.rows_read + 1,
+                committed_rows
+            )));
+        }
+    }
+
+    println!(
+        "📥 Imported {} rows into {} ({} rejected)",
+        result.rows_inserted, table_name, result.rows_rejected
+    );
+    Ok(result)
+}
+
+/// Field names of a JSON object in file order (`serde_json::Map` sorts them)
+struct FieldNames(Vec<String>);
+
+impl<'de> serde::Deserialize<'de> for FieldNames {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = FieldNames;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<FieldNames, A::Error> {
+                let mut names = Vec::new();
+                while let Some((key, _)) = map.next_entry::<String, serde::de::IgnoredAny>()? {
+                    names.push(key);
+                }
+                Ok(FieldNames(names))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+/// Read the source columns and the first records. For CSV the columns come from the
+/// header; for JSON Lines they are all fields seen in the sample, in file order.
+fn sample_source(
+    file_path: &str,
+    format: ImportFormat,
+    options: &ImportOptions,
+) -> anyhow::Result<(Vec<String>, Vec<Vec<SourceValue>>)> {
+    let columns = match format {
+        ImportFormat::Csv => csv_reader(file_path, options)?
+            .headers()?
+            .iter()
+            .map(|h| h.trim().to_string())
+            .collect(),
+        ImportFormat::JsonLines => {
+            let mut columns: Vec<String> = Vec::new();
+            let file =
+                File::open(file_path).with_context(|| format!("Failed to open {}", file_path))?;
+            for line in BufReader::new(file).lines().take(SAMPLE_SIZE) {
+                if let Ok(FieldNames(keys)) = serde_json::from_str(&line?) {
+                    for key in keys {
+                        if !columns.contains(&key) {
+                            columns.push(key);
+                        }
+                    }
+                }
+            }
+            columns
+        }
+    };
+
+    let sample = read_source(file_path, format, &columns, options)?
+        .take(SAMPLE_SIZE)
+        .filter_map(|record| record.ok().and_then(|r| r.values.ok()))
+        .collect();
+
+    Ok((columns, sample))
+}
+
+fn csv_reader(file_path: &str, options: &ImportOptions) -> anyhow::Result<csv::Reader<File>> {
+    let delimiter = options.delimiter.unwrap_or(',');
+    if !delimiter.is_ascii() {
+        bail!("CSV delimiter must be a single ASCII character");
+    }
+    let file = File::open(file_path).with_context(|| format!("Failed to open {}", file_path))?;
+    Ok(csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .from_reader(file))
+}
+
+/// Stream the records of the file. Malformed records are yielded with the reason so they
+/// can be reported; only I/O errors end the iteration.
+fn read_source<'a>(
+    file_path: &str,
+    format: ImportFormat,
+    columns: &'a [String],
+    options: &ImportOptions,
+) -> anyhow::Result<Box<dyn Iterator<Item = anyhow::Result<SourceRecord>> + 'a>> {
+    match format {
+        ImportFormat::Csv => {
+            let null_value = options.null_value.clone();
+            let reader = csv_reader(file_path, options)?;
+            Ok(Box::new(reader.into_records().map(move |record| {
+                match record {
+                    Ok(record) => Ok(SourceRecord {
+                        line: record.position().map(|p| p.line()).unwrap_or_default(),
+                        values: Ok(record
+                            .iter()
+                            .map(|field| {
+                                let is_null = match &null_value {
+                                    Some(null_value) => field == null_value,
+                                    None => field.is_empty() || field == CSV_NULL,
+                                };
+                                if is_null {
+                                    SourceValue::Null
+                                } else {
+                                    SourceValue::Text(field.to_string())
+                                }
+                            })
+                            .collect()),
+                    }),
+                    Err(e) if e.is_io_error() => Err(e.into()),
+                    Err(e) => Ok(SourceRecord {
+                        line: e.position().map(|p| p.line()).unwrap_or_default(),
+                        values: Err(e.to_string()),
+                    }),
+                }
+            })))
+        }
+        ImportFormat::JsonLines => {
+            let file =
+                File::open(file_path).with_context(|| format!("Failed to open {}", file_path))?;
+            let lines = BufReader::new(file)
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()));
+            Ok(Box::new(lines.map(move |(index, line)| {
+                Ok(SourceRecord {
+                    line: index as u64 + 1,
+                    values: json_record(&line?, columns),
+                })
+            })))
+        }
+    }
+}
+
+fn json_record(line: &str, columns: &[String]) -> Result<Vec<SourceValue>, String> {
+    let mut object = match serde_json::from_str(line) {
+        Ok(serde_json::Value::Object(object)) => object,
+        Ok(_) => return Err("Line is not a JSON object".to_string()),
+        Err(e) => return Err(format!("Invalid JSON: {}", e)),
+    };
+
+    let values = columns
+        .iter()
+        .map(|column| match object.remove(column) {
+            None | Some(serde_json::Value::Null) => SourceValue::Null,
+            Some(value) => SourceValue::Json(value),
+        })
+        .collect();
+
+    match object.keys().next() {
+        Some(unknown) => Err(format!("Unexpected field '{}'", unknown)),
+        None => Ok(values),
+    }
+}
+
+/// Convert a source value for its target column. Values that SQLite would silently store
+/// as text in a numeric column are rejected instead.
+fn convert(value: &SourceValue, column: &ColumnInfo) -> Result<Value, String> {
+    let affinity = Affinity::of(&column.data_type);
+
+    match value {
+        SourceValue::Null => Ok(Value::Null),
+        SourceValue::Text(text) => match affinity {
+            Affinity::Integer | Affinity::Real | Affinity::Numeric => {
+                let trimmed = text.trim();
+                if let Ok(i) = trimmed.parse::<i64>() {
+                    Ok(Value::Integer(i))
+                } else if let Ok(f) = trimmed.parse::<f64>() {
+                    Ok(Value::Real(f))
+                } else if affinity == Affinity::Numeric {
+                    // NUMERIC columns commonly hold dates and the like
+                    Ok(Value::Text(text.clone()))
+                } else {
+                    Err(format!(
+                        "'{}' is not a number (column '{}')",
+                        text, column.name
+                    ))
+                }
+            }
+            // Blobs are exported as base64; anything else is kept as text
+            Affinity::Blob if column.data_type.to_uppercase().contains("BLOB") => {
+                Ok(BASE64_STANDARD
+                    .decode(text)
+                    .map(Value::Blob)
+                    .unwrap_or_else(|_| Value::Text(text.clone())))
+            }
+            Affinity::Text | Affinity::Blob => Ok(Value::Text(text.clone())),
+        },
+        SourceValue::Json(json) => match json {
+            serde_json::Value::Bool(b) => Ok(Value::Integer(*b as i64)),
+            serde_json::Value::Number(n) => Ok(match n.as_i64() {
+                Some(i) => Value::Integer(i),
+                None => Value::Real(n.as_f64().unwrap_or_default()),
+            }),
+            // Strings go through the same checks as CSV fields
+            serde_json::Value::String(s) => convert(&SourceValue::Text(s.clone()), column),
+            serde_json::Value::Object(object) => {
+                match object.get("blob").and_then(|b| b.as_str()) {
+                    Some(blob) => BASE64_STANDARD
+                        .decode(blob)
+                        .map(Value::Blob)
+                        .map_err(|_| format!("Invalid base64 blob (column '{}')", column.name)),
+                    // Nested objects are stored as JSON text
+                    None => Ok(Value::Text(json.to_string())),
+                }
+            }
+            serde_json::Value::Array(_) => Ok(Value::Text(json.to_string())),
+            serde_json::Value::Null => Ok(Value::Null),
+        },
+    }
+}
+
+fn create_table(
+    conn: &Connection,
+    table_name: &str,
+    source_columns: &[String],
+    sample: &[Vec<SourceValue>],
+    options: &ImportOptions,
+) -> anyhow::Result<()> {
+    let definitions: Vec<String> = source_columns
+        .iter()
+        .enumerate()
+        .map(|(index, source_column)| {
+            let name = options
+                .column_mapping
+                .get(source_column)
+                .unwrap_or(source_column);
+            let data_type = infer_type(sample.iter().map(|row| &row[index]));
+            format!("{} {}", quote_identifier(name), data_type)
+        })
+        .collect();
+
+    let sql = format!(
+        "CREATE TABLE {} ({})",
+        quote_identifier(table_name),
+        definitions.join(", ")
+    );
+    conn.execute(&sql, [])?;
+    println!("📋 Created table for import: {}", sql);
+    Ok(())
+}
+
+/// Narrowest of INTEGER, REAL, TEXT (and BLOB for JSON blobs) that fits every sampled value
+fn infer_type<'a>(values: impl Iterator<Item = &'a SourceValue>) -> &'static str {
+    let (mut integer, mut real, mut text, mut blob) = (false, false, false, false);
+
+    for value in values {
+        match value {
+            SourceValue::Null => {}
+            SourceValue::Text(s) => {
+                let s = s.trim();
+                if s.parse::<i64>().is_ok() {
+                    integer = true;
+                } else if s.parse::<f64>().is_ok() {
+                    real = true;
+                } else {
+                    text = true;
+                }
+            }
+            SourceValue::Json(json) => match json {
+                serde_json::Value::Bool(_) => integer = true,
+                serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => integer = true,
+                serde_json::Value::Number(_) => real = true,
+                serde_json::Value::Object(object) if object.contains_key("blob") => blob = true,
+                _ => text = true,
+            },
+        }
+    }
+
+    match (blob, text, real, integer) {
+        (true, false, false, false) => "BLOB",
+        (false, false, false, true) => "INTEGER",
+        (false, false, true, _) => "REAL",
+        _ => "TEXT",
+    }
+}
@@ -0,0 +1,341 @@
+// src-tauri/src/integrity.rs
+use crate::database::{hex, DatabaseManager};
+use crate::migrations::{patch_hash, MIGRATIONS_TABLE};
+use crate::models::{
+    ComparisonFilter, ComparisonRules, DataComparisonResult, PatchHeader, PatchVerification,
+};
+use crate::rowdiff::walk_rows;
+use anyhow::{bail, Context};
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the header layout; patches with a newer header are refused
+pub const HEADER_FORMAT_VERSION: u32 = 1;
+
+/// Start of a patch's first line, followed by its `PatchHeader` as JSON
+pub const HEADER_TAG: &str = "-- plandb-patch: ";
+
+pub fn generator() -> String {
+    format!("planDB {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Header for a patch generated from `template` for `apply_to` under `filter` and
+/// `rules`, expecting `apply_to`'s current schema; the body hash is filled in when the
+/// patch is written
+pub fn new_header(
+    manager: &DatabaseManager,
+    template: &str,
+    apply_to: &str,
+    filter: &ComparisonFilter,
+    rules: &ComparisonRules,
+) -> anyhow::Result<PatchHeader> {
+    let fingerprint = |path: &str| -> anyhow::Result<String> {
+        let conn = manager.reader(path)?;
+        manager
+            .schema_fingerprint(&conn)
+            .with_context(|| format!("Failed to read schema of {}", path))
+    };
+    Ok(PatchHeader {
+        format_version: HEADER_FORMAT_VERSION,
+        generator: generator(),
+        source_schema: Some(fingerprint(template)?),
+        target_schema: Some(fingerprint(apply_to)?),
+        template: Some(template.to_string()),
+        filter: filter.clone(),
+        rules: rules.clone(),
+        body_sha256: String::new(),
+    })
+}
+
+/// The header of a patch and the body it covers, or `None` for a patch without one
+pub fn split_header(patch_sql: &str) -> anyhow::Result<Option<(PatchHeader, &str)>> {
+    let Some(rest) = patch_sql.strip_prefix(HEADER_TAG) else {
+        return Ok(None);
+    };
+    let (json, body) = rest.split_once('\n').unwrap_or((rest, ""));
+    let header: PatchHeader =
+        serde_json::from_str(json.trim_end()).context("Patch header is not valid")?;
+    if header.format_version > HEADER_FORMAT_VERSION {
+        bail!(
+            "Patch was written by a newer version ({}, header format {})",
+            header.generator,
+            header.format_version
+        );
+    }
+    Ok(Some((header, body)))
+}
+
+/// The patch with `header` as its first line, for patches built in memory
+pub fn sign(mut header: PatchHeader, body: &str) -> String {
+    header.format_version = HEADER_FORMAT_VERSION;
+    header.generator = generator();
+    header.body_sha256 = patch_hash(body);
+    let json = serde_json::to_string(&header).unwrap_or_default();
+    format!("{}{}\n{}", HEADER_TAG, json, body)
+}
+
+/// Whether the patch is still what was generated. Patches without a header can't be
+/// checked and pass.
+pub fn verify_body(patch_sql: &str) -> anyhow::Result<Option<String>> {
+    let Some((header, body)) = split_header(patch_sql)? else {
+        println!("⚠️ Patch has no integrity header, applying it unverified");
+        return Ok(None);
+    };
+    if patch_hash(body) != header.body_sha256 {
+        return Ok(Some(
+            "The patch was modified after it was generated".to_string(),
+        ));
+    }
+    Ok(None)
+}
+
+/// Whether `target` still has the schema the patch was generated for. Meant to run
+/// before any statement of the patch; patches that don't say what they expect pass.
+pub fn check_schema(
+    manager: &DatabaseManager,
+    patch_sql: &str,
+    target: &Connection,
+) -> anyhow::Result<Option<String>> {
+    let Some(expected) = split_header(patch_sql)?.and_then(|(header, _)| header.target_schema)
+    else {
+        return Ok(None);
+    };
+    let actual = manager.schema_fingerprint(target)?;
+    if actual != expected {
+        return Ok(Some(format!(
+            "The target database's schema has changed since the patch was generated \
+             (expected fingerprint {}, found {})",
+            expected, actual
+        )));
+    }
+    Ok(None)
+}
+
+/// Compare a patched database with its template again, under the filter and rules the
+/// patch was generated with. Rows of the tables with the same schema are compared too
+/// if `include_data` is set.
+pub fn verify_applied(
+    manager: &DatabaseManager,
+    template: &str,
+    target: &str,
+    filter: &ComparisonFilter,
+    rules: &ComparisonRules,
+    include_data: bool,
+) -> anyhow::Result<PatchVerification> {
+    let mut schema = manager.compare_schemas(template, target, filter, rules)?;
+    // Tracking adds the migrations table to the target only
+    schema.added_tables.retain(|t| t != MIGRATIONS_TABLE);
+    schema.removed_tables.retain(|t| t != MIGRATIONS_TABLE);
+
+    let mut data = Vec::new();
+    if include_data {
+        for table in &schema.identical_tables {
+            let result = compare_rows(manager, template, target, table, filter, rules)
+                .with_context(|| format!("Failed to compare rows of {}", table))?;
+            if !result.identical {
+                data.push(result);
+            }
+        }
+    }
+
+    Ok(PatchVerification {
+        template: template.to_string(),
+        schema,
+        data,
+    })
+}
+
+/// Rows of a table in the template and the patched database, matched on the primary
+/// key (whole rows for keyless tables). Ignored columns are left out.
+fn compare_rows(
+    manager: &DatabaseManager,
+    template: &str,
+    target: &str,
+    table: &str,
+    filter: &ComparisonFilter,
+    rules: &ComparisonRules,
+) -> anyhow::Result<DataComparisonResult> {
+    let conn1 = manager.reader(template)?;
+    let conn2 = manager.reader(target)?;
+    let (mut key, mut columns) = (Vec::new(), Vec::new());
+    for column in manager.get_table_columns(&conn1, table)? {
+        if column.is_primary_key {
+            key.push(column.name);
+        } else if !filter.ignores_column(table, &column.name) {
+            columns.push(column.name);
+        }
+    }
+    if key.is_empty() {
+        key = std::mem::take(&mut columns);
+    }
+
+    let mut result = DataComparisonResult {
+        table_name: table.to_string(),
+        total_rows_db1: 0,
+        total_rows_db2: 0,
+        rows_inserted: 0,
+        rows_deleted: 0,
+        rows_potentially_modified: 0,
+        identical: false,
+    };
+    walk_rows(
+        &conn1,
+        &conn2,
+        table,
+        &key,
+        [&columns, &columns],
+        None,
+        |row1, row2| {
+            match (row1, row2) {
+                (Some(r1), Some(r2)) => {
+                    let modified = r1[key.len()..]
+                        .iter()
+                        .zip(&r2[key.len()..])
+                        .any(|(a, b)| !rules.values_equal(a, b));
+                    if modified {
+                        result.rows_potentially_modified += 1;
+                    }
+                }
+                (Some(_), None) => result.rows_deleted += 1,
+                (None, Some(_)) => result.rows_inserted += 1,
+                (None, None) => {}
+            }
+            result.total_rows_db1 += row1.is_some() as i64;
+            result.total_rows_db2 += row2.is_some() as i64;
+            Ok(())
+        },
+    )?;
+    result.identical = result.rows_inserted == 0
+        && result.rows_deleted == 0
+        && result.rows_potentially_modified == 0;
+    Ok(result)
+}
+
+impl PatchVerification {
+    pub fn is_clean(&self) -> bool {
+        self.schema.added_tables.is_empty()
+            && self.schema.removed_tables.is_empty()
+            && self.schema.modified_tables.is_empty()
+            && self.data.is_empty()
+    }
+
+    /// One line per remaining difference, for the apply result
+    pub fn summary(&self) -> String {
+        if self.is_clean() {
+            return format!("Verified: the database now matches {}.", self.template);
+        }
+
+        let mut lines = vec![format!(
+            "Verification found differences from {}:",
+            self.template
+        )];
+        let mut sorted = |tables: &[String], what: &str| {
+            let mut tables = tables.to_vec();
+            tables.sort();
+            if !tables.is_empty() {
+                lines.push(format!("- {}: {}", what, tables.join(", ")));
+            }
+        };
+        sorted(&self.schema.removed_tables, "Tables still missing");
+        sorted(&self.schema.added_tables, "Tables not in the template");
+        for diff in &self.schema.modified_tables {
+            let mut changes = Vec::new();
+            if !diff.removed_columns.is_empty() {
+                changes.push(format!(
+                    "missing columns {}",
+                    diff.removed_columns.join(", ")
+                ));
+            }
+            if !diff.added_columns.is_empty() {
+                let names: Vec<&str> = diff.added_columns.iter().map(|c| c.name.as_str()).collect();
+                changes.push(format!("extra columns {}", names.join(", ")));
+            }
+            for column in &diff.modified_columns {
+                changes.push(format!(
+                    "{} ({})",
+                    column.column_name,
+                    column.changes.join(", ")
+                ));
+            }
+            if diff.column_order_changed {
+                changes.push("column order".to_string());
+            }
+            changes.extend(diff.option_changes.iter().cloned());
+            lines.push(format!(
+                "- Table {}: {}",
+                diff.table_name,
+                changes.join("; ")
+            ));
+        }
+        for table in &self.data {
+            lines.push(format!(
+                "- Rows of {} differ ({} in the template, {} here)",
+                table.table_name, table.total_rows_db1, table.total_rows_db2
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Writes a patch body to `<path>.part`, hashing it on the way. `finish` then writes
+/// the patch to `path` with its header in front.
+pub struct SignedPatchWriter {
+    out: BufWriter<File>,
+    hasher: Sha256,
+    path: PathBuf,
+    part_path: PathBuf,
+}
+
+impl SignedPatchWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut part_path = path.as_os_str().to_owned();
+        part_path.push(".part");
+        let part_path = PathBuf::from(part_path);
+        Ok(Self {
+            out: BufWriter::new(File::create(&part_path)?),
+            hasher: Sha256::new(),
+            path: path.to_path_buf(),
+            part_path,
+        })
+    }
+
+    pub fn finish(self, mut header: PatchHeader) -> anyhow::Result<PatchHeader> {
+        let Self {
+            mut out,
+            hasher,
+            path,
+            part_path,
+        } = self;
+        out.flush()?;
+        drop(out);
+        header.format_version = HEADER_FORMAT_VERSION;
+        header.generator = generator();
+        header.body_sha256 = hex(&hasher.finalize());
+
+        let written = (|| -> anyhow::Result<()> {
+            let mut file = BufWriter::new(File::create(&path)?);
+            writeln!(file, "{}{}", HEADER_TAG, serde_json::to_string(&header)?)?;
+            io::copy(&mut File::open(&part_path)?, &mut file)?;
+            file.flush()?;
+            Ok(())
+        })();
+        let _ = fs::remove_file(&part_path);
+        written.with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(header)
+    }
+}
+
+impl Write for SignedPatchWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
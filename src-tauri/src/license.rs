@@ -223,7 +223,7 @@ fn get_machine_id() -> String {
     format!("{:x}", result)
 }
 
-fn get_app_data_dir() -> Result<PathBuf, String> {
+pub fn get_app_data_dir() -> Result<PathBuf, String> {
     let app_name = "SQLCipherTool";
     
     #[cfg(target_os = "windows")]
@@ -1,19 +1,39 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod browse;
 mod commands;
 mod database;
+mod dump;
+mod editing;
+mod export;
+mod filter;
+mod importer;
+mod integrity;
 mod license;
+mod merge;
+mod migrations;
 mod models;
+mod profiles;
+mod report;
+mod rollback;
+mod rowdiff;
+mod rules;
+mod snapshot;
+mod sync;
+mod watcher;
 
 use database::DatabaseManager;
+use editing::EditLog;
 use license::LicenseManager;
+use profiles::ProfileManager;
 use std::sync::Mutex;
+use tauri::Manager;
 use tauri_plugin_dialog;
 
 fn main() {
     // Initialize database manager
-    let db_manager = Mutex::new(DatabaseManager::new());
+    let db_manager = DatabaseManager::new();
 
     // Initialize license manager
     let license_manager = match LicenseManager::new() {
@@ -24,6 +44,15 @@ fn main() {
         }
     };
 
+    // Initialize saved connection profiles
+    let profile_manager = match ProfileManager::new() {
+        Ok(manager) => Mutex::new(manager),
+        Err(e) => {
+            eprintln!("Failed to initialize profile manager: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     tauri::Builder::default()
         // .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
@@ -31,19 +60,43 @@ fn main() {
         .plugin(tauri_plugin_opener::init())
         .manage(db_manager)
         .manage(license_manager)
+        .manage(profile_manager)
+        .manage(EditLog::new())
+        .setup(|app| {
+            // Watch connected databases for changes made outside the app
+            watcher::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::test_connection,
             commands::connect_database,
+            commands::disconnect_database,
+            commands::list_connections,
+            commands::check_database_changes,
             commands::get_database_tables,
             commands::get_table_data,
+            commands::browse_table_data,
+            commands::execute_query,
+            commands::export_table_data,
+            commands::import_table_data,
+            commands::save_schema_snapshot,
+            commands::dump_database,
+            commands::merge_databases,
+            commands::sync_databases,
+            commands::generate_comparison_report,
             commands::get_table_info,
             commands::compare_database_schemas,
             commands::compare_table_data_fast,
+            commands::compare_table_rows,
             commands::generate_schema_patch,
+            commands::generate_reversible_schema_patch,
             commands::generate_table_schema_patch, // Added this line
             commands::apply_schema_patch,
+            commands::apply_migration_directory,
+            commands::get_migration_history,
             commands::generate_data_patch,
             commands::generate_data_patch_file,
+            commands::generate_reversible_data_patch,
             commands::apply_data_patch,
             commands::apply_patch_file,
             commands::save_temp_file,
@@ -56,7 +109,28 @@ fn main() {
             commands::check_database_type,
             commands::check_installation_status,
             commands::get_trial_info,
+            commands::list_connection_profiles,
+            commands::save_connection_profile,
+            commands::rename_connection_profile,
+            commands::delete_connection_profile,
+            commands::set_profile_key_columns,
+            commands::get_workspace,
+            commands::save_workspace,
+            commands::open_workspace,
+            commands::update_table_row,
+            commands::insert_table_row,
+            commands::delete_table_row,
+            commands::undo_last_edit,
+            commands::get_edit_history,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Stop the watcher and close every connection cleanly
+                watcher::stop();
+                app_handle.state::<DatabaseManager>().close_all();
+                println!("👋 Closed all database connections");
+            }
+        });
 }
@@ -0,0 +1,1014 @@
+// src-tauri/src/merge.rs
+use crate::browse::quote_identifier;
+use crate::database::{table_options, typed_value_to_json, DatabaseManager};
+use crate::dump::{order_views, shadow_tables, sql_literal};
+use crate::models::{
+    ColumnInfo, ComparisonFilter, MergeAction, MergeChange, MergeConflict, MergeConflictKind,
+    MergeResult, MergeTableStats,
+};
+use anyhow::Context;
+use rusqlite::types::Value;
+use rusqlite::{Connection, Rows};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+/// Conflicts reported in full; the rest are only counted
+const MAX_REPORTED_CONFLICTS: usize = 500;
+
+const BASE: usize = 0;
+const OURS: usize = 1;
+const THEIRS: usize = 2;
+
+/// Three-way merge of `theirs` into `ours` against their common ancestor `base`.
+/// Changes made on only one side are taken, changes both sides made differently are
+/// reported as conflicts (keeping ours). The patch at `output_path` applies to ours.
+/// Tables the filter excludes are left out, and its ignored columns count as unchanged.
+pub fn merge(
+    manager: &DatabaseManager,
+    base_path: &str,
+    ours_path: &str,
+    theirs_path: &str,
+    key_columns: &HashMap<String, String>,
+    filter: &ComparisonFilter,
+    output_path: &str,
+) -> anyhow::Result<MergeResult> {
+    let base = manager
+        .reader(base_path)
+        .context("Base database not connected")?;
+    let ours = manager
+        .reader(ours_path)
+        .context("Our database not connected")?;
+    let theirs = manager
+        .reader(theirs_path)
+        .context("Their database not connected")?;
+
+    let mut merger = Merger {
+        manager,
+        conns: [&base, &ours, &theirs],
+        snapshots: [
+            read_snapshot(&base)?,
+            read_snapshot(&ours)?,
+            read_snapshot(&theirs)?,
+        ],
+        key_columns,
+        filter,
+        schema_changes: Vec::new(),
+        data_changes: Vec::new(),
+        conflicts: Vec::new(),
+        total_conflicts: 0,
+    };
+
+    let written = File::create(output_path)
+        .with_context(|| format!("Failed to create {}", output_path))
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            merger.write_patch(&mut out, base_path, ours_path, theirs_path)?;
+            out.flush()?;
+            Ok(())
+        });
+    if let Err(e) = written {
+        let _ = fs::remove_file(output_path);
+        return Err(e);
+    }
+
+    println!(
+        "🔀 Merged {} into {} (base {}): {} schema changes, {} conflicts",
+        theirs_path,
+        ours_path,
+        base_path,
+        merger.schema_changes.len(),
+        merger.total_conflicts
+    );
+
+    Ok(MergeResult {
+        base_path: base_path.to_string(),
+        ours_path: ours_path.to_string(),
+        theirs_path: theirs_path.to_string(),
+        patch_path: output_path.to_string(),
+        schema_changes: merger.schema_changes,
+        data_changes: merger.data_changes,
+        conflicts: merger.conflicts,
+        total_conflicts: merger.total_conflicts,
+    })
+}
+
+#[derive(Default)]
+struct Snapshot {
+    tables: HashMap<String, String>,
+    /// `(type, name) -> (table, sql)` of indexes, views and triggers
+    others: HashMap<(String, String), (String, String)>,
+}
+
+fn read_snapshot(conn: &Connection) -> anyhow::Result<Snapshot> {
+    let shadow = shadow_tables(conn)?;
+    let mut snapshot = Snapshot::default();
+
+    let mut stmt =
+        conn.prepare("SELECT type, name, tbl_name, sql FROM sqlite_master WHERE sql IS NOT NULL")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let kind: String = row.get(0)?;
+        let name: String = row.get(1)?;
+        let table: String = row.get(2)?;
+        let sql: String = row.get(3)?;
+        if table.starts_with("sqlite_") || shadow.contains(&table) {
+            continue;
+        }
+        if kind == "table" {
+            snapshot.tables.insert(name, sql);
+        } else {
+            snapshot.others.insert((kind, name), (table, sql));
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Definitions compare equal regardless of formatting
+fn normalized(sql: Option<&String>) -> Option<String> {
+    sql.map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Rows of one table to merge, once the schema is settled
+struct TableMerge {
+    table: String,
+    /// Columns of the table in ours once the schema part of the patch ran
+    merged: Vec<String>,
+    key: Vec<String>,
+    /// Per merged column, whether the filter ignores it when comparing rows
+    ignored: Vec<bool>,
+    /// Whether the table exists in base, ours and theirs
+    present: [bool; 3],
+}
+
+#[derive(Default)]
+struct RowStats {
+    inserted: u64,
+    updated: u64,
+    deleted: u64,
+    /// Rows that differ from base on each side
+    ours_changed: u64,
+    theirs_changed: u64,
+}
+
+struct MergeRow {
+    key: Vec<Value>,
+    /// One cell per merged column. Where the version lacks the column it holds the
+    /// column's default, which is what adding the column would give the row.
+    cells: Vec<Value>,
+}
+
+struct Merger<'a> {
+    manager: &'a DatabaseManager,
+    conns: [&'a Connection; 3],
+    snapshots: [Snapshot; 3],
+    key_columns: &'a HashMap<String, String>,
+    filter: &'a ComparisonFilter,
+    schema_changes: Vec<MergeChange>,
+    data_changes: Vec<MergeTableStats>,
+    conflicts: Vec<MergeConflict>,
+    total_conflicts: u64,
+}
+
+impl Merger<'_> {
+    fn write_patch(
+        &mut self,
+        out: &mut impl Write,
+        base_path: &str,
+        ours_path: &str,
+        theirs_path: &str,
+    ) -> anyhow::Result<()> {
+        // Indexes, views and triggers: drop what they changed or removed before touching
+        // tables, create their versions once the tables are in place
+        let mut drop_objects = Vec::new();
+        let mut create_objects = Vec::new();
+        let mut views = Vec::new();
+        let mut kept_objects = Vec::new();
+
+        let names: BTreeSet<(String, String)> = self
+            .snapshots
+            .iter()
+            .flat_map(|s| s.others.keys().cloned())
+            .collect();
+        for (kind, name) in names {
+            let versions = self
+                .snapshots
+                .each_ref()
+                .map(|s| s.others.get(&(kind.clone(), name.clone())).cloned());
+            let owner = versions.iter().flatten().map(|(table, _)| table).next();
+            if kind != "view" && owner.is_some_and(|table| !self.filter.includes_table(table)) {
+                if let Some(ours) = &versions[OURS] {
+                    kept_objects.push(ours.clone());
+                }
+                continue;
+            }
+            let [b, o, t] = versions
+                .each_ref()
+                .map(|v| normalized(v.as_ref().map(|(_, sql)| sql)));
+
+            if o == t || t == b {
+                if let Some(ours) = &versions[OURS] {
+                    kept_objects.push(ours.clone());
+                }
+                continue;
+            }
+            if o != b {
+                let kind_of_conflict = conflict_kind(&b, &o, &t);
+                self.add_conflict(MergeConflict {
+                    kind: kind_of_conflict,
+                    object_type: kind.clone(),
+                    name: name.clone(),
+                    key: None,
+                    columns: Vec::new(),
+                    base: b.map(serde_json::Value::String),
+                    ours: o.map(serde_json::Value::String),
+                    theirs: t.map(serde_json::Value::String),
+                });
+                if let Some(ours) = &versions[OURS] {
+                    kept_objects.push(ours.clone());
+                }
+                continue;
+            }
+
+            // Only they changed it
+            if o.is_some() {
+                drop_objects.push(format!(
+                    "DROP {} IF EXISTS {};",
+                    kind.to_uppercase(),
+                    quote_identifier(&name)
+                ));
+            }
+            match &versions[THEIRS] {
+                Some((_, sql)) if kind == "view" => views.push((name.clone(), sql.clone())),
+                Some((_, sql)) => create_objects.push(format!("{};", sql)),
+                None => {}
+            }
+            self.schema_changes.push(MergeChange {
+                object_type: kind,
+                name,
+                action: if t.is_some() {
+                    MergeAction::Create
+                } else {
+                    MergeAction::Drop
+                },
+            });
+        }
+        let view_sql: Vec<String> = order_views(views)
+            .into_iter()
+            .map(|sql| format!("{};", sql))
+            .collect();
+        // Views before triggers, which may be INSTEAD OF triggers on a view
+        let trigger_at = create_objects
+            .iter()
+            .position(|sql| sql.to_uppercase().starts_with("CREATE TRIGGER"))
+            .unwrap_or(create_objects.len());
+        create_objects.splice(trigger_at..trigger_at, view_sql);
+
+        // Tables
+        let mut table_sql = Vec::new();
+        let mut drop_tables = Vec::new();
+        let mut row_merges = Vec::new();
+
+        let tables: BTreeSet<String> = self
+            .snapshots
+            .iter()
+            .flat_map(|s| s.tables.keys().cloned())
+            .filter(|table| self.filter.includes_table(table))
+            .collect();
+        for table in tables {
+            self.plan_table(
+                &table,
+                &kept_objects,
+                &mut table_sql,
+                &mut drop_tables,
+                &mut row_merges,
+            )?;
+        }
+
+        writeln!(out, "-- Three-way Merge Patch")?;
+        writeln!(out, "-- Base: {}", base_path)?;
+        writeln!(out, "-- Theirs: {}", theirs_path)?;
+        writeln!(out, "-- Apply to: {} (Ours)", ours_path)?;
+        writeln!(out, "-- Generated: {}", chrono::Utc::now())?;
+        writeln!(out)?;
+        writeln!(out, "BEGIN TRANSACTION;\n")?;
+
+        for sql in drop_objects.iter().chain(&table_sql) {
+            writeln!(out, "{}", sql)?;
+        }
+        writeln!(out)?;
+
+        for plan in &row_merges {
+            let stats = self.merge_rows(plan, out)?;
+            if stats.inserted + stats.updated + stats.deleted > 0 {
+                self.data_changes.push(MergeTableStats {
+                    table_name: plan.table.clone(),
+                    rows_inserted: stats.inserted,
+                    rows_updated: stats.updated,
+                    rows_deleted: stats.deleted,
+                });
+            }
+        }
+
+        for sql in drop_tables.iter().chain(&create_objects) {
+            writeln!(out, "{}", sql)?;
+        }
+
+        writeln!(out, "\nCOMMIT;")?;
+        if self.total_conflicts > 0 {
+            writeln!(
+                out,
+                "\n-- {} conflicts left unresolved (ours kept)",
+                self.total_conflicts
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Decide what happens to one table's schema and queue its rows for merging
+    fn plan_table(
+        &mut self,
+        table: &str,
+        kept_objects: &[(String, String)],
+        table_sql: &mut Vec<String>,
+        drop_tables: &mut Vec<String>,
+        row_merges: &mut Vec<TableMerge>,
+    ) -> anyhow::Result<()> {
+        let sqls = self
+            .snapshots
+            .each_ref()
+            .map(|s| s.tables.get(table).cloned());
+        let [b, o, t] = sqls.each_ref().map(|sql| normalized(sql.as_ref()));
+        let columns = [
+            self.columns(BASE, table)?,
+            self.columns(OURS, table)?,
+            self.columns(THEIRS, table)?,
+        ];
+        let present = [b.is_some(), o.is_some(), t.is_some()];
+        let quoted = quote_identifier(table);
+
+        let schema_conflict = |kind| MergeConflict {
+            kind,
+            object_type: "table".to_string(),
+            name: table.to_string(),
+            key: None,
+            columns: Vec::new(),
+            base: b.clone().map(serde_json::Value::String),
+            ours: o.clone().map(serde_json::Value::String),
+            theirs: t.clone().map(serde_json::Value::String),
+        };
+
+        if o == t {
+            if let Some(ours) = &columns[OURS] {
+                row_merges.push(self.table_merge(table, column_names(ours), present));
+            }
+        } else if o == b {
+            // Only they changed the table
+            match (&columns[OURS], &columns[THEIRS]) {
+                (Some(ours), None) => {
+                    let plan = self.table_merge(table, column_names(ours), present);
+                    if self.count_changes(&plan)?.ours_changed > 0 {
+                        self.add_conflict(schema_conflict(MergeConflictKind::ModifyDelete));
+                    } else {
+                        drop_tables.push(format!("DROP TABLE IF EXISTS {};", quoted));
+                        self.schema_change(table, MergeAction::Drop);
+                    }
+                }
+                (None, Some(theirs)) => {
+                    table_sql.push(format!("{};", theirs_sql(&sqls)));
+                    self.schema_change(table, MergeAction::Create);
+                    row_merges.push(self.table_merge(table, column_names(theirs), present));
+                }
+                (Some(ours), Some(theirs)) => {
+                    let base = columns[BASE].as_deref().unwrap_or_default();
+                    match appended_columns(base, theirs) {
+                        Some(added) if !added.is_empty() => {
+                            table_sql.extend(added.iter().map(|c| add_column_sql(table, c)));
+                            self.schema_change(table, MergeAction::AddColumns);
+                            let mut merged = column_names(ours);
+                            merged.extend(column_names(added));
+                            row_merges.push(self.table_merge(table, merged, present));
+                        }
+                        _ => {
+                            let plan = self.table_merge(table, column_names(theirs), present);
+                            // Row statements address rowid-keyed rows by their old rowid,
+                            // so the rebuild has to keep it
+                            let keep_rowid =
+                                plan.key.iter().any(|k| k.eq_ignore_ascii_case("rowid"));
+                            if keep_rowid
+                                && table_options(theirs_sql(&sqls))
+                                    .0
+                                    .iter()
+                                    .any(|o| o == "WITHOUT ROWID")
+                            {
+                                self.add_conflict(schema_conflict(MergeConflictKind::MissingKey));
+                                return Ok(());
+                            }
+                            table_sql.push(recreate_table_sql(
+                                table,
+                                theirs_sql(&sqls),
+                                &column_names(ours),
+                                &column_names(theirs),
+                                keep_rowid,
+                            ));
+                            // Dropping the old table took its indexes and triggers along
+                            table_sql.extend(
+                                kept_objects
+                                    .iter()
+                                    .filter(|(owner, sql)| {
+                                        owner == table
+                                            && !sql.to_uppercase().starts_with("CREATE VIEW")
+                                    })
+                                    .map(|(_, sql)| format!("{};", sql)),
+                            );
+                            self.schema_change(table, MergeAction::Recreate);
+                            row_merges.push(plan);
+                        }
+                    }
+                }
+                (None, None) => {}
+            }
+        } else if t == b {
+            // Only we changed the table; their row changes still apply
+            match (&columns[OURS], &columns[THEIRS]) {
+                (None, Some(theirs)) => {
+                    let plan = self.table_merge(table, column_names(theirs), present);
+                    if self.count_changes(&plan)?.theirs_changed > 0 {
+                        self.add_conflict(schema_conflict(MergeConflictKind::DeleteModify));
+                    }
+                }
+                (Some(ours), _) => {
+                    row_merges.push(self.table_merge(table, column_names(ours), present));
+                }
+                (None, None) => {}
+            }
+        } else {
+            // Both changed it: fine as long as both only appended compatible columns
+            let appended = match (&columns[BASE], &columns[OURS], &columns[THEIRS]) {
+                (Some(base), Some(ours), Some(theirs)) => {
+                    match (appended_columns(base, ours), appended_columns(base, theirs)) {
+                        (Some(ours_added), Some(theirs_added)) => {
+                            compatible_additions(ours_added, theirs_added)
+                                .map(|added| (ours, added))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            match appended {
+                Some((ours, added)) => {
+                    if !added.is_empty() {
+                        table_sql.extend(added.iter().map(|c| add_column_sql(table, c)));
+                        self.schema_change(table, MergeAction::AddColumns);
+                    }
+                    let mut merged = column_names(ours);
+                    merged.extend(column_names(&added));
+                    row_merges.push(self.table_merge(table, merged, present));
+                }
+                None => self.add_conflict(schema_conflict(conflict_kind(&b, &o, &t))),
+            }
+        }
+        Ok(())
+    }
+
+    fn columns(&self, side: usize, table: &str) -> anyhow::Result<Option<Vec<ColumnInfo>>> {
+        if !self.snapshots[side].tables.contains_key(table) {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.manager.get_table_columns(self.conns[side], table)?,
+        ))
+    }
+
+    /// Rows are matched on the key column override, else the primary key, else the rowid
+    fn table_merge(&self, table: &str, merged: Vec<String>, present: [bool; 3]) -> TableMerge {
+        let key = match self.key_columns.get(table) {
+            Some(column) => vec![column.clone()],
+            None => {
+                let side = [OURS, THEIRS, BASE]
+                    .into_iter()
+                    .find(|&side| present[side])
+                    .unwrap_or(OURS);
+                let primary_key: Vec<String> = self
+                    .columns(side, table)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|c| c.is_primary_key)
+                    .map(|c| c.name)
+                    .collect();
+                if primary_key.is_empty() {
+                    vec!["rowid".to_string()]
+                } else {
+                    primary_key
+                }
+            }
+        };
+        let ignored = merged
+            .iter()
+            .map(|c| self.filter.ignores_column(table, c))
+            .collect();
+        TableMerge {
+            table: table.to_string(),
+            merged,
+            key,
+            ignored,
+            present,
+        }
+    }
+
+    fn schema_change(&mut self, table: &str, action: MergeAction) {
+        self.schema_changes.push(MergeChange {
+            object_type: "table".to_string(),
+            name: table.to_string(),
+            action,
+        });
+    }
+
+    fn add_conflict(&mut self, conflict: MergeConflict) {
+        self.total_conflicts += 1;
+        if self.conflicts.len() < MAX_REPORTED_CONFLICTS {
+            self.conflicts.push(conflict);
+        }
+    }
+
+    /// Row merge without writing anything or recording conflicts
+    fn count_changes(&mut self, plan: &TableMerge) -> anyhow::Result<RowStats> {
+        let (conflicts, total) = (std::mem::take(&mut self.conflicts), self.total_conflicts);
+        let stats = self.merge_rows(plan, &mut io::sink());
+        self.conflicts = conflicts;
+        self.total_conflicts = total;
+        stats
+    }
+
+    /// Walk the table in key order on all three sides at once and write the
+    /// statements that bring their row changes into ours
+    fn merge_rows(&mut self, plan: &TableMerge, out: &mut impl Write) -> anyhow::Result<RowStats> {
+        let mut stats = RowStats::default();
+        let quoted_key: Vec<String> = plan.key.iter().map(|k| quote_identifier(k)).collect();
+
+        let side_columns = [
+            self.columns(BASE, &plan.table)?,
+            self.columns(OURS, &plan.table)?,
+            self.columns(THEIRS, &plan.table)?,
+        ];
+        let defaults: Vec<Value> = plan
+            .merged
+            .iter()
+            .map(|name| {
+                let default = [THEIRS, OURS, BASE]
+                    .iter()
+                    .filter_map(|&side| side_columns[side].as_ref())
+                    .flatten()
+                    .find(|c| &c.name == name)
+                    .and_then(|c| c.default_value.clone());
+                default
+                    .and_then(|expr| {
+                        self.conns[OURS]
+                            .query_row(&format!("SELECT {}", expr), [], |row| row.get(0))
+                            .ok()
+                    })
+                    .unwrap_or(Value::Null)
+            })
+            .collect();
+
+        let mut statements = Vec::with_capacity(3);
+        for side in [BASE, OURS, THEIRS] {
+            let Some(columns) = &side_columns[side] else {
+                statements.push(None);
+                continue;
+            };
+            let columns = column_names(columns);
+            let has_key = plan.key.iter().all(|k| {
+                k.eq_ignore_ascii_case("rowid") || columns.iter().any(|c| c.eq_ignore_ascii_case(k))
+            });
+            if !has_key {
+                self.add_conflict(MergeConflict {
+                    kind: MergeConflictKind::MissingKey,
+                    object_type: "table".to_string(),
+                    name: plan.table.clone(),
+                    key: None,
+                    columns: plan.key.clone(),
+                    base: None,
+                    ours: None,
+                    theirs: None,
+                });
+                return Ok(stats);
+            }
+
+            // Merged columns this version has, after the key
+            let mut select = quoted_key.clone();
+            let mut positions = Vec::with_capacity(plan.merged.len());
+            for name in &plan.merged {
+                if columns.contains(name) {
+                    positions.push(Some(select.len()));
+                    select.push(quote_identifier(name));
+                } else {
+                    positions.push(None);
+                }
+            }
+            let order_by: Vec<String> = quoted_key
+                .iter()
+                .map(|k| format!("{} COLLATE BINARY", k))
+                .collect();
+            let sql = format!(
+                "SELECT {} FROM {} ORDER BY {}",
+                select.join(", "),
+                quote_identifier(&plan.table),
+                order_by.join(", ")
+            );
+            let stmt = self.conns[side]
+                .prepare(&sql)
+                .with_context(|| format!("Failed to read {}", plan.table))?;
+            statements.push(Some((stmt, positions)));
+        }
+
+        let key_len = plan.key.len();
+        let mut sides: Vec<Option<(Rows, Vec<Option<usize>>)>> = Vec::with_capacity(3);
+        for statement in statements.iter_mut() {
+            sides.push(match statement {
+                Some((stmt, positions)) => Some((stmt.query([])?, positions.clone())),
+                None => None,
+            });
+        }
+
+        let mut current = [
+            next_row(&mut sides[BASE], key_len, &defaults)?,
+            next_row(&mut sides[OURS], key_len, &defaults)?,
+            next_row(&mut sides[THEIRS], key_len, &defaults)?,
+        ];
+        // The smallest key on any side is the next row to decide
+        while let Some(key) = current
+            .iter()
+            .flatten()
+            .map(|row| &row.key)
+            .min_by(|a, b| compare_keys(a, b))
+            .cloned()
+        {
+            let mut rows: [Option<MergeRow>; 3] = [None, None, None];
+            for side in [BASE, OURS, THEIRS] {
+                let matches = current[side]
+                    .as_ref()
+                    .is_some_and(|row| compare_keys(&row.key, &key) == Ordering::Equal);
+                if matches {
+                    rows[side] = current[side].take();
+                    current[side] = next_row(&mut sides[side], key_len, &defaults)?;
+                }
+            }
+            self.merge_row(plan, &key, &rows, out, &mut stats)?;
+        }
+        Ok(stats)
+    }
+
+    fn merge_row(
+        &mut self,
+        plan: &TableMerge,
+        key: &[Value],
+        rows: &[Option<MergeRow>; 3],
+        out: &mut impl Write,
+        stats: &mut RowStats,
+    ) -> anyhow::Result<()> {
+        let cells = |side: usize| rows[side].as_ref().map(|row| &row.cells);
+        let (b, o, t) = (cells(BASE), cells(OURS), cells(THEIRS));
+        // Rows that only differ in ignored columns are the same row
+        let same = |x: Option<&Vec<Value>>, y: Option<&Vec<Value>>| match (x, y) {
+            (Some(x), Some(y)) => (0..x.len()).all(|i| plan.ignored[i] || x[i] == y[i]),
+            (x, y) => x.is_none() && y.is_none(),
+        };
+        if !same(o, b) {
+            stats.ours_changed += 1;
+        }
+        if !same(t, b) {
+            stats.theirs_changed += 1;
+        }
+        if same(o, t) || same(t, b) {
+            return Ok(());
+        }
+
+        let quoted_table = quote_identifier(&plan.table);
+        let where_clause = plan
+            .key
+            .iter()
+            .zip(key)
+            .map(|(k, v)| format!("{} IS {}", quote_identifier(k), sql_literal(v)))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        let conflict_kind = match (b, o, t) {
+            _ if same(o, b) => None,
+            (Some(b), Some(o), Some(t)) => {
+                let conflicting: Vec<String> = plan
+                    .merged
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| {
+                        !plan.ignored[i] && o[i] != t[i] && o[i] != b[i] && t[i] != b[i]
+                    })
+                    .map(|(_, name)| name.clone())
+                    .collect();
+                if conflicting.is_empty() {
+                    // Different columns changed on each side: take theirs where we kept base
+                    let changes: Vec<(usize, &Value)> = (0..plan.merged.len())
+                        .filter(|&i| !plan.ignored[i] && o[i] == b[i] && t[i] != b[i])
+                        .map(|i| (i, &t[i]))
+                        .collect();
+                    if write_update(out, plan, &quoted_table, &changes, &where_clause)? {
+                        stats.updated += 1;
+                    }
+                    return Ok(());
+                }
+                self.add_row_conflict(plan, key, rows, MergeConflictKind::RowChanged, conflicting);
+                return Ok(());
+            }
+            (Some(_), Some(_), None) => Some(MergeConflictKind::ModifyDelete),
+            (Some(_), None, Some(_)) => Some(MergeConflictKind::DeleteModify),
+            _ => Some(MergeConflictKind::RowAdded),
+        };
+        if let Some(kind) = conflict_kind {
+            self.add_row_conflict(plan, key, rows, kind, Vec::new());
+            return Ok(());
+        }
+
+        // We left the row as it was in base: take theirs
+        match (o, t) {
+            (Some(o), Some(t)) => {
+                let changes: Vec<(usize, &Value)> = (0..plan.merged.len())
+                    .filter(|&i| !plan.ignored[i] && t[i] != o[i])
+                    .map(|i| (i, &t[i]))
+                    .collect();
+                if write_update(out, plan, &quoted_table, &changes, &where_clause)? {
+                    stats.updated += 1;
+                }
+            }
+            (Some(_), None) => {
+                writeln!(out, "DELETE FROM {} WHERE {};", quoted_table, where_clause)?;
+                stats.deleted += 1;
+            }
+            (None, Some(t)) => {
+                // Key columns that aren't regular columns (the rowid) keep their value too
+                let mut names = Vec::new();
+                let mut values = Vec::new();
+                for (k, v) in plan.key.iter().zip(key) {
+                    if !plan.merged.contains(k) {
+                        names.push(quote_identifier(k));
+                        values.push(sql_literal(v));
+                    }
+                }
+                for (name, value) in plan.merged.iter().zip(t) {
+                    names.push(quote_identifier(name));
+                    values.push(sql_literal(value));
+                }
+                writeln!(
+                    out,
+                    "INSERT INTO {}({}) VALUES({});",
+                    quoted_table,
+                    names.join(", "),
+                    values.join(", ")
+                )?;
+                stats.inserted += 1;
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    fn add_row_conflict(
+        &mut self,
+        plan: &TableMerge,
+        key: &[Value],
+        rows: &[Option<MergeRow>; 3],
+        kind: MergeConflictKind,
+        columns: Vec<String>,
+    ) {
+        let row_json = |side: usize| {
+            rows[side].as_ref().map(|row| {
+                let object: serde_json::Map<String, serde_json::Value> = plan
+                    .merged
+                    .iter()
+                    .zip(&row.cells)
+                    .map(|(name, value)| (name.clone(), typed_value_to_json(value.clone())))
+                    .collect();
+                serde_json::Value::Object(object)
+            })
+        };
+        self.add_conflict(MergeConflict {
+            kind,
+            object_type: "row".to_string(),
+            name: plan.table.clone(),
+            key: Some(
+                plan.key
+                    .iter()
+                    .zip(key)
+                    .map(|(k, v)| (k.clone(), typed_value_to_json(v.clone())))
+                    .collect(),
+            ),
+            columns,
+            base: row_json(BASE),
+            ours: row_json(OURS),
+            theirs: row_json(THEIRS),
+        });
+    }
+}
+
+fn write_update(
+    out: &mut impl Write,
+    plan: &TableMerge,
+    quoted_table: &str,
+    changes: &[(usize, &Value)],
+    where_clause: &str,
+) -> anyhow::Result<bool> {
+    let set_clauses: Vec<String> = changes
+        .iter()
+        .map(|(i, v)| {
+            format!(
+                "{} = {}",
+                quote_identifier(&plan.merged[*i]),
+                sql_literal(v)
+            )
+        })
+        .collect();
+    if !set_clauses.is_empty() {
+        writeln!(
+            out,
+            "UPDATE {} SET {} WHERE {};",
+            quoted_table,
+            set_clauses.join(", "),
+            where_clause
+        )?;
+    }
+    Ok(!set_clauses.is_empty())
+}
+
+fn next_row(
+    side: &mut Option<(Rows, Vec<Option<usize>>)>,
+    key_len: usize,
+    defaults: &[Value],
+) -> anyhow::Result<Option<MergeRow>> {
+    let Some((rows, positions)) = side else {
+        return Ok(None);
+    };
+    let Some(row) = rows.next()? else {
+        return Ok(None);
+    };
+    let key = (0..key_len)
+        .map(|i| row.get::<_, Value>(i))
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let cells = positions
+        .iter()
+        .zip(defaults)
+        .map(|(position, default)| match position {
+            Some(i) => row.get::<_, Value>(*i),
+            None => Ok(default.clone()),
+        })
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(Some(MergeRow { key, cells }))
+}
+
+pub(crate) fn compare_keys(a: &[Value], b: &[Value]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| compare_values(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Same order as SQLite's `ORDER BY ... COLLATE BINARY`
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Integer(_) | Value::Real(_) => 1,
+            Value::Text(_) => 2,
+            Value::Blob(_) => 3,
+        }
+    }
+    match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+        (Value::Integer(_) | Value::Real(_), Value::Integer(_) | Value::Real(_)) => {
+            as_f64(a).partial_cmp(&as_f64(b)).unwrap_or(Ordering::Equal)
+        }
+        (Value::Text(a), Value::Text(b)) => a.as_bytes().cmp(b.as_bytes()),
+        (Value::Blob(a), Value::Blob(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn as_f64(value: &Value) -> f64 {
+    match value {
+        Value::Integer(i) => *i as f64,
+        Value::Real(f) => *f,
+        _ => 0.0,
+    }
+}
+
+fn conflict_kind(b: &Option<String>, o: &Option<String>, t: &Option<String>) -> MergeConflictKind {
+    match (b, o, t) {
+        (Some(_), Some(_), None) => MergeConflictKind::ModifyDelete,
+        (Some(_), None, Some(_)) => MergeConflictKind::DeleteModify,
+        _ => MergeConflictKind::SchemaChanged,
+    }
+}
+
+fn theirs_sql(sqls: &[Option<String>; 3]) -> &str {
+    sqls[THEIRS].as_deref().unwrap_or_default()
+}
+
+fn column_names(columns: &[ColumnInfo]) -> Vec<String> {
+    columns.iter().map(|c| c.name.clone()).collect()
+}
+
+fn same_column(a: &ColumnInfo, b: &ColumnInfo) -> bool {
+    a.name == b.name
+        && a.data_type.eq_ignore_ascii_case(&b.data_type)
+        && a.is_nullable == b.is_nullable
+        && a.default_value == b.default_value
+        && a.is_primary_key == b.is_primary_key
+}
+
+/// Columns `changed` appended to `base`, if that's all that changed and
+/// `ALTER TABLE ... ADD COLUMN` can add them
+fn appended_columns<'c>(
+    base: &[ColumnInfo],
+    changed: &'c [ColumnInfo],
+) -> Option<&'c [ColumnInfo]> {
+    if changed.len() < base.len() || !base.iter().zip(changed).all(|(a, b)| same_column(a, b)) {
+        return None;
+    }
+    let added = &changed[base.len()..];
+    let addable = added
+        .iter()
+        .all(|c| !c.is_primary_key && (c.is_nullable || c.default_value.is_some()));
+    addable.then_some(added)
+}
+
+/// Their appended columns we don't have yet; `None` if both added a column of the
+/// same name with different definitions
+fn compatible_additions(ours: &[ColumnInfo], theirs: &[ColumnInfo]) -> Option<Vec<ColumnInfo>> {
+    let mut added = Vec::new();
+    for column in theirs {
+        match ours.iter().find(|c| c.name == column.name) {
+            Some(existing) if same_column(existing, column) => {}
+            Some(_) => return None,
+            None => added.push(column.clone()),
+        }
+    }
+    Some(added)
+}
+
+fn add_column_sql(table: &str, column: &ColumnInfo) -> String {
+    let nullable = if column.is_nullable { "" } else { " NOT NULL" };
+    let default = match &column.default_value {
+        Some(def) => format!(" DEFAULT {}", def),
+        None => String::new(),
+    };
+    format!(
+        "ALTER TABLE {} ADD COLUMN {} {}{}{};",
+        quote_identifier(table),
+        quote_identifier(&column.name),
+        column.data_type,
+        nullable,
+        default
+    )
+}
+
+/// Rebuild a table with their definition, keeping the data of the common columns.
+/// Legacy rename keeps views and triggers that mention the table pointing at its name.
+/// `keep_rowid` copies the rowid too, for tables whose rows are matched on it.
+fn recreate_table_sql(
+    table: &str,
+    create_sql: &str,
+    ours: &[String],
+    theirs: &[String],
+    keep_rowid: bool,
+) -> String {
+    let quoted = quote_identifier(table);
+    let old = quote_identifier(&format!("{}_merge_old", table));
+    let mut common: Vec<String> = theirs
+        .iter()
+        .filter(|c| ours.contains(c))
+        .map(|c| quote_identifier(c))
+        .collect();
+    if keep_rowid {
+        common.insert(0, "rowid".to_string());
+    }
+
+    let mut sql = String::new();
+    sql.push_str("PRAGMA legacy_alter_table=ON;\n");
+    sql.push_str(&format!("ALTER TABLE {} RENAME TO {};\n", quoted, old));
+    sql.push_str(&format!("{};\n", create_sql));
+    if !common.is_empty() {
+        sql.push_str(&format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {};\n",
+            quoted,
+            common.join(", "),
+            common.join(", "),
+            old
+        ));
+    }
+    sql.push_str(&format!("DROP TABLE {};\n", old));
+    sql.push_str("PRAGMA legacy_alter_table=OFF;");
+    sql
+}
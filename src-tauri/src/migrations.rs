@@ -0,0 +1,171 @@
+// src-tauri/src/migrations.rs
+use crate::database::hex;
+use crate::integrity::HEADER_TAG;
+use crate::models::MigrationRecord;
+use anyhow::{bail, Context};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Table in the target database that records applied patches
+pub const MIGRATIONS_TABLE: &str = "plandb_migrations";
+
+const CREATE_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS plandb_migrations (
+    id INTEGER PRIMARY KEY,
+    patch_hash TEXT NOT NULL UNIQUE,
+    name TEXT,
+    direction TEXT,
+    source TEXT,
+    applied_at TEXT NOT NULL,
+    statement_count INTEGER NOT NULL,
+    version INTEGER
+)";
+
+const SELECT_SQL: &str = "SELECT id, patch_hash, name, direction, source, applied_at, \
+     statement_count, version FROM plandb_migrations";
+
+pub fn patch_hash(patch_sql: &str) -> String {
+    hex(&Sha256::digest(patch_sql.as_bytes()))
+}
+
+/// A record for `patch_sql`, described by its comment header (title line, `Direction:`
+/// and `Template:`/`Theirs:`; the integrity line is skipped). `statement_count` is
+/// filled in once it has run.
+pub fn new_record(patch_sql: &str) -> MigrationRecord {
+    let header: Vec<&str> = patch_sql
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.is_empty() || line.starts_with("--"))
+        .filter(|line| !line.starts_with(HEADER_TAG.trim_end()))
+        .filter_map(|line| line.strip_prefix("--").map(str::trim))
+        .filter(|line| !line.is_empty())
+        .collect();
+    let value = |label: &str| {
+        header.iter().find_map(|line| {
+            let rest = line.strip_prefix(label)?.strip_prefix(':')?;
+            Some(rest.trim().to_string())
+        })
+    };
+
+    MigrationRecord {
+        id: 0,
+        patch_hash: patch_hash(patch_sql),
+        name: header.first().map(|line| line.to_string()),
+        direction: value("Direction"),
+        source: value("Template").or_else(|| value("Theirs")),
+        applied_at: chrono::Utc::now(),
+        statement_count: 0,
+        version: None,
+    }
+}
+
+/// Whether the database has a migrations table, i.e. already tracks its patches
+pub fn is_tracked(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [MIGRATIONS_TABLE],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+pub fn find_applied(
+    conn: &Connection,
+    patch_hash: &str,
+) -> rusqlite::Result<Option<MigrationRecord>> {
+    if !is_tracked(conn)? {
+        return Ok(None);
+    }
+    conn.query_row(
+        &format!("{} WHERE patch_hash = ?1", SELECT_SQL),
+        [patch_hash],
+        read_record,
+    )
+    .optional()
+}
+
+/// Record an applied patch, creating the migrations table on first use. Meant to run
+/// inside the patch's last transaction so the record commits with it.
+pub fn record(conn: &Connection, record: &MigrationRecord) -> rusqlite::Result<()> {
+    conn.execute(CREATE_TABLE_SQL, [])?;
+    conn.execute(
+        "INSERT INTO plandb_migrations \
+         (patch_hash, name, direction, source, applied_at, statement_count, version) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            record.patch_hash,
+            record.name,
+            record.direction,
+            record.source,
+            record.applied_at.to_rfc3339(),
+            record.statement_count,
+            record.version,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Applied patches, oldest first
+pub fn history(conn: &Connection) -> rusqlite::Result<Vec<MigrationRecord>> {
+    if !is_tracked(conn)? {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare(&format!("{} ORDER BY id", SELECT_SQL))?;
+    let records = stmt
+        .query_map([], read_record)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(records)
+}
+
+fn read_record(row: &Row) -> rusqlite::Result<MigrationRecord> {
+    let applied_at: String = row.get(5)?;
+    Ok(MigrationRecord {
+        id: row.get(0)?,
+        patch_hash: row.get(1)?,
+        name: row.get(2)?,
+        direction: row.get(3)?,
+        source: row.get(4)?,
+        applied_at: chrono::DateTime::parse_from_rfc3339(&applied_at)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .unwrap_or_default(),
+        statement_count: row.get(6)?,
+        version: row.get(7)?,
+    })
+}
+
+/// `.sql` files in `dir` whose names start with a version number (`001_init.sql`,
+/// `2-add-index.sql`), in version order. Other files are ignored.
+pub fn migration_files(dir: &Path) -> anyhow::Result<Vec<(i64, PathBuf)>> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let is_sql = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("sql"));
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let digits: String = name.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if !is_sql || digits.is_empty() {
+            continue;
+        }
+        let version: i64 = digits
+            .parse()
+            .with_context(|| format!("Version number of {} is too large", name))?;
+        files.push((version, path));
+    }
+
+    files.sort();
+    if let Some(pair) = files.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        bail!(
+            "{} and {} have the same version {}",
+            pair[0].1.display(),
+            pair[1].1.display(),
+            pair[0].0
+        );
+    }
+    Ok(files)
+}
@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataComparisonResult {
@@ -11,6 +13,28 @@ pub struct DataComparisonResult {
     pub identical: bool,
 }
 
+/// Rows of one table in both databases, matched on a key column. Same shape as the
+/// comparison the data patch generator reads (`generate_data_patch`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRowComparison {
+    pub common_columns: Vec<String>,
+    pub source_only_columns: Vec<String>,
+    pub target_only_columns: Vec<String>,
+    pub different_rows: Vec<DifferentRow>,
+    pub missing_in_target: Vec<serde_json::Map<String, serde_json::Value>>,
+    pub extra_in_target: Vec<serde_json::Map<String, serde_json::Value>>,
+    pub identical_rows: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DifferentRow {
+    pub source_row: serde_json::Map<String, serde_json::Value>,
+    pub target_row: serde_json::Map<String, serde_json::Value>,
+    pub different_columns: Vec<String>,
+}
+
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseInfo {
@@ -21,6 +45,22 @@ pub struct DatabaseInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub alias: Option<String>,
     pub password: Option<String>, 
+    #[serde(default)]
+    pub access_mode: AccessMode,
+}
+
+/// How the app's own connections to a database are opened. Writes (patches,
+/// migrations) always go through a separate, explicitly opened connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessMode {
+    /// `SQLITE_OPEN_READ_ONLY` / `?mode=ro` - the default for browsing and comparison
+    #[default]
+    ReadOnly,
+    /// `?immutable=1` - for snapshots that are guaranteed not to change while open
+    Immutable,
+    /// Regular read-write connection
+    ReadWrite,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +68,12 @@ pub struct TableInfo {
     pub name: String,
     pub row_count: i64,
     pub columns: Vec<ColumnInfo>,
+    /// `STRICT`, `WITHOUT ROWID` and `AUTOINCREMENT`, where the table uses them
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Module of a virtual table (`fts5`, `rtree`, ...)
+    #[serde(default)]
+    pub virtual_module: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +83,9 @@ pub struct ColumnInfo {
     pub is_nullable: bool,
     pub default_value: Option<String>,
     pub is_primary_key: bool,
+    /// `VIRTUAL` or `STORED` for generated columns
+    #[serde(default)]
+    pub generated: Option<String>,
 }
 
 // Existing array-based TableData (for browse/schema)
@@ -55,6 +104,392 @@ pub struct TableDataObject {
     pub total_count: i64,
 }
 
+/// One page of a cursor-paginated browse
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TablePage {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Filtered row count; only computed for the first page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<i64>,
+    /// Opaque token for the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Result of an ad-hoc query from the SQL console
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub columns: Vec<QueryColumn>,
+    /// Cells keep their SQLite type: integers, reals, text, null, blobs as `{ "blob": base64 }`
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// True if the row cap cut the result short
+    pub truncated: bool,
+    pub elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_plan: Option<Vec<QueryPlanStep>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryColumn {
+    pub name: String,
+    /// Declared type of the underlying table column, `None` for expressions
+    pub declared_type: Option<String>,
+}
+
+/// One row of `EXPLAIN QUERY PLAN`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPlanStep {
+    pub id: i64,
+    pub parent: i64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+    Parquet,
+}
+
+/// What to export: a (filtered/sorted) table, or the result of a read-only query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportSource {
+    Table {
+        table_name: String,
+        #[serde(default)]
+        query: Option<TableQuery>,
+    },
+    Query {
+        sql: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+    /// CSV only: text written for NULL (default: `\N`, so NULL and empty strings stay
+    /// apart)
+    #[serde(default)]
+    pub null_value: Option<String>,
+    /// CSV only: field delimiter (default `,`)
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Stop after this many rows
+    #[serde(default)]
+    pub max_rows: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportResult {
+    pub file_path: String,
+    pub format: ExportFormat,
+    pub columns: Vec<String>,
+    pub rows_written: u64,
+    pub file_size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    Csv,
+    JsonLines,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportOptions {
+    /// Create the table from the file's columns and inferred types if it doesn't exist
+    #[serde(default)]
+    pub create_table: bool,
+    /// File column -> table column; unmapped file columns go to the column of the same name
+    #[serde(default)]
+    pub column_mapping: HashMap<String, String>,
+    /// CSV only: text that stands for NULL (default: an empty field or `\N`)
+    #[serde(default)]
+    pub null_value: Option<String>,
+    /// CSV only: field delimiter (default `,`)
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Rows per transaction (default 1000)
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+    /// Stop after this many rows
+    #[serde(default)]
+    pub max_rows: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedRow {
+    /// 1-based line in the file (for CSV, the line the record starts on)
+    pub line: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub table_name: String,
+    pub created_table: bool,
+    /// Table columns that received data
+    pub columns: Vec<String>,
+    /// File columns without a matching table column
+    pub ignored_columns: Vec<String>,
+    pub rows_read: u64,
+    pub rows_inserted: u64,
+    pub rows_rejected: u64,
+    /// The first rejected rows with the reason (capped, see `rows_rejected` for the total)
+    pub rejected: Vec<RejectedRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpResult {
+    pub file_path: String,
+    pub tables: usize,
+    pub rows_written: u64,
+    pub file_size: u64,
+}
+
+/// The schema of a database, and optionally a hash of every row, saved as JSON so live
+/// databases can be compared against it later (e.g. the version that was shipped)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSnapshot {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Database the snapshot was taken from
+    pub database_path: String,
+    pub tables: Vec<TableInfo>,
+    /// Table -> row hashes; empty unless the snapshot was saved with them
+    #[serde(default)]
+    pub row_hashes: HashMap<String, TableRowHashes>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableRowHashes {
+    /// Primary key; empty for tables without one, which only get `table_hash`
+    pub key_columns: Vec<String>,
+    /// Columns that were hashed, in order
+    pub columns: Vec<String>,
+    pub row_count: u64,
+    /// Hash over all rows in key order
+    pub table_hash: String,
+    /// Key (as a JSON array) -> row hash
+    #[serde(default)]
+    pub rows: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotResult {
+    pub file_path: String,
+    pub tables: usize,
+    pub rows_hashed: u64,
+    pub file_size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeAction {
+    Create,
+    Drop,
+    /// `ALTER TABLE ... ADD COLUMN` for columns appended on their side
+    AddColumns,
+    /// Table rebuilt with their definition, keeping the common columns' data
+    Recreate,
+}
+
+/// A schema change from their side that the merge patch applies to ours
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeChange {
+    /// `table`, `index`, `view` or `trigger`
+    pub object_type: String,
+    pub name: String,
+    pub action: MergeAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeTableStats {
+    pub table_name: String,
+    pub rows_inserted: u64,
+    pub rows_updated: u64,
+    pub rows_deleted: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeConflictKind {
+    /// Both sides changed the definition of the same object differently
+    SchemaChanged,
+    /// We changed a table or row that they dropped
+    ModifyDelete,
+    /// They changed a table or row that we dropped
+    DeleteModify,
+    /// Both sides changed the same columns of a row differently
+    RowChanged,
+    /// Both sides added a row with the same key but different values
+    RowAdded,
+    /// The key column doesn't exist in every version of the table
+    MissingKey,
+}
+
+/// A change the merge could not decide; ours is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub kind: MergeConflictKind,
+    /// `table`, `index`, `view`, `trigger` or `row`
+    pub object_type: String,
+    /// Object name, or the table of a row conflict
+    pub name: String,
+    /// Row conflicts: the row's key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Row conflicts: the columns both sides changed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    /// Definition (SQL) or row on each side, `None` where it doesn't exist.
+    /// Row values use the SQL console encoding (blobs as `{ "blob": base64 }`).
+    pub base: Option<serde_json::Value>,
+    pub ours: Option<serde_json::Value>,
+    pub theirs: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeResult {
+    pub base_path: String,
+    pub ours_path: String,
+    pub theirs_path: String,
+    /// SQL patch that brings their non-conflicting changes into ours
+    pub patch_path: String,
+    pub schema_changes: Vec<MergeChange>,
+    pub data_changes: Vec<MergeTableStats>,
+    /// The first conflicts (capped, see `total_conflicts` for the count)
+    pub conflicts: Vec<MergeConflict>,
+    pub total_conflicts: u64,
+}
+
+/// Which side's row wins when a row exists on both sides with different values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncConflictPolicy {
+    #[default]
+    SourceWins,
+    TargetWins,
+    /// The row with the larger `updated_at` value wins; ties are left alone
+    Newest,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncOptions {
+    /// Tables to sync (default: every table both databases have)
+    #[serde(default)]
+    pub tables: Option<Vec<String>>,
+    /// Per-table key column overrides (table -> column); default is the primary key
+    #[serde(default)]
+    pub key_columns: HashMap<String, String>,
+    #[serde(default)]
+    pub policy: SyncConflictPolicy,
+    /// Timestamp column for the `newest` policy (default `updated_at`)
+    #[serde(default)]
+    pub updated_at_column: Option<String>,
+    /// Rows whose values are equal under these rules are left alone
+    #[serde(default)]
+    pub rules: ComparisonRules,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncTableStats {
+    pub table_name: String,
+    pub inserted_into_source: u64,
+    pub inserted_into_target: u64,
+    pub updated_in_source: u64,
+    pub updated_in_target: u64,
+    /// Differing rows the policy couldn't decide (`newest` with equal timestamps)
+    pub unresolved: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResult {
+    /// Patch to apply to the source database
+    pub source_patch_path: String,
+    /// Patch to apply to the target database
+    pub target_patch_path: String,
+    pub policy: SyncConflictPolicy,
+    pub tables: Vec<SyncTableStats>,
+    /// Tables that couldn't be synced, e.g. without a primary key or key column override
+    pub skipped_tables: Vec<SkippedTable>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedTable {
+    pub table_name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowEditKind {
+    Update,
+    Insert,
+    Delete,
+}
+
+/// A single-row edit made from the data grid, kept in the session's undo log.
+/// Row values use the SQL console encoding (blobs as `{ "blob": base64 }`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowEdit {
+    pub id: u64,
+    pub db_path: String,
+    pub table_name: String,
+    pub kind: RowEditKind,
+    /// Identifies the row after the edit (before it, for deletes): `{ "rowid": n }` or the primary key
+    pub key: serde_json::Map<String, serde_json::Value>,
+    pub before: Option<serde_json::Map<String, serde_json::Value>>,
+    pub after: Option<serde_json::Map<String, serde_json::Value>>,
+    pub edited_at: DateTime<Utc>,
+}
+
+/// Sorting, filtering and search applied when browsing a table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableQuery {
+    #[serde(default)]
+    pub order_by: Vec<SortColumn>,
+    #[serde(default)]
+    pub filters: Vec<ColumnFilter>,
+    /// Case-insensitive substring search across all text columns
+    #[serde(default)]
+    pub search: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SortColumn {
+    pub column: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnFilter {
+    pub column: String,
+    pub operator: FilterOperator,
+    /// Operand; an array for `in`, ignored for `is_null` / `is_not_null`
+    #[serde(default)]
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOperator {
+    Eq,
+    NotEq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Contains,
+    StartsWith,
+    EndsWith,
+    In,
+    IsNull,
+    IsNotNull,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaComparison {
     pub database1: String,
@@ -65,12 +500,56 @@ pub struct SchemaComparison {
     pub identical_tables: Vec<String>,
 }
 
+/// Tables and columns left out of schema diffs, data diffs and patches. Patterns are
+/// case-insensitive globs (`*`, `?`); column patterns may name a table (`audit_*.changed_by`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComparisonFilter {
+    /// Only compare matching tables (default: all)
+    #[serde(default)]
+    pub include_tables: Vec<String>,
+    #[serde(default)]
+    pub exclude_tables: Vec<String>,
+    /// Columns whose differences are ignored, e.g. `updated_at`
+    #[serde(default)]
+    pub ignored_columns: Vec<String>,
+}
+
+/// How loosely types and values are compared. The default is an exact comparison
+/// (types case-insensitively).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComparisonRules {
+    /// Declared types are equal if SQLite gives them the same affinity
+    /// (`INT` = `INTEGER`, `VARCHAR(255)` = `TEXT`)
+    #[serde(default)]
+    pub type_affinity: bool,
+    /// Numbers are equal if they differ by at most this much (`1` and `1.0` always are)
+    #[serde(default)]
+    pub numeric_tolerance: Option<f64>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Ignore leading and trailing whitespace in text
+    #[serde(default)]
+    pub trim_whitespace: bool,
+    /// Text that parses as a date/time is compared as an instant, whatever its format
+    #[serde(default)]
+    pub normalize_timestamps: bool,
+    /// A table whose shared columns only differ in order counts as identical
+    #[serde(default)]
+    pub ignore_column_order: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableDiff {
     pub table_name: String,
     pub added_columns: Vec<ColumnInfo>,
     pub removed_columns: Vec<String>,
     pub modified_columns: Vec<ColumnDiff>,
+    /// The columns both versions have are in a different order
+    #[serde(default)]
+    pub column_order_changed: bool,
+    /// Table options or virtual table module that differ, e.g. `STRICT: no -> yes`
+    #[serde(default)]
+    pub option_changes: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,9 +557,161 @@ pub struct ColumnDiff {
     pub column_name: String,
     pub old_type: String,
     pub new_type: String,
+    #[serde(default)]
+    pub old_default: Option<String>,
+    #[serde(default)]
+    pub new_default: Option<String>,
     pub changes: Vec<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    /// Self-contained page (inline styles, no scripts)
+    Html,
+    /// For pull requests and issues
+    Markdown,
+    /// `ComparisonReport` as is, for tooling
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportOptions {
+    #[serde(default)]
+    pub filter: ComparisonFilter,
+    #[serde(default)]
+    pub rules: ComparisonRules,
+    /// Differing rows listed per table (default 10)
+    #[serde(default)]
+    pub max_samples: Option<usize>,
+    /// Leave out row statistics and samples
+    #[serde(default)]
+    pub schema_only: bool,
+}
+
+/// A full comparison as written by the report generator. The JSON form is a stable
+/// interface: `format_version` is bumped whenever a field changes meaning or goes away.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparisonReport {
+    pub format_version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub database1: String,
+    pub database2: String,
+    pub schema: SchemaComparison,
+    pub tables: Vec<TableDataReport>,
+}
+
+/// Row statistics for a table both databases have. `inserted` rows are only in
+/// database 2, `deleted` rows only in database 1.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableDataReport {
+    pub table_name: String,
+    pub key_columns: Vec<String>,
+    pub rows_db1: u64,
+    pub rows_db2: u64,
+    pub rows_inserted: u64,
+    pub rows_deleted: u64,
+    pub rows_modified: u64,
+    pub samples: Vec<RowSample>,
+    /// Why the rows weren't compared (e.g. no primary key)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowChangeKind {
+    Inserted,
+    Deleted,
+    Modified,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowSample {
+    pub kind: RowChangeKind,
+    pub key: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row_db1: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row_db2: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Modified rows only
+    #[serde(default)]
+    pub different_columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportResult {
+    pub file_path: String,
+    pub format: ReportFormat,
+    pub tables_compared: usize,
+    pub tables_with_differences: usize,
+    pub file_size: u64,
+}
+
+/// A patch applied to a database, as recorded in its `plandb_migrations` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationRecord {
+    pub id: i64,
+    /// SHA-256 of the patch text; the same patch is never applied twice
+    pub patch_hash: String,
+    /// Migration file name, or the title line of the patch
+    pub name: Option<String>,
+    pub direction: Option<String>,
+    /// Template database the patch was generated from
+    pub source: Option<String>,
+    pub applied_at: DateTime<Utc>,
+    pub statement_count: i64,
+    /// `user_version` set by a numbered migration file
+    pub version: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationRunResult {
+    pub applied: Vec<MigrationRecord>,
+    /// Files at or below the database's `user_version`, which were already applied
+    pub skipped: Vec<String>,
+    pub user_version: i64,
+}
+
+/// Machine-readable first line of a generated patch file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PatchHeader {
+    pub format_version: u32,
+    /// Application name and version that wrote the patch
+    pub generator: String,
+    /// Schema fingerprint of the template database
+    pub source_schema: Option<String>,
+    /// Schema fingerprint of the database the patch applies to, before applying it
+    pub target_schema: Option<String>,
+    /// Template database, which the patched database is checked against afterwards
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Filter and rules the patch was generated with, so verifying it compares alike
+    #[serde(default)]
+    pub filter: ComparisonFilter,
+    #[serde(default)]
+    pub rules: ComparisonRules,
+    /// SHA-256 of everything after the header line
+    pub body_sha256: String,
+}
+
+/// What still differs between a patched database and its template after applying
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchVerification {
+    pub template: String,
+    pub schema: SchemaComparison,
+    /// Tables whose rows still differ; only filled in when data was verified
+    pub data: Vec<DataComparisonResult>,
+}
+
+/// A schema patch together with the patch that undoes it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReversibleSchemaPatch {
+    pub forward: String,
+    /// Applied to the same database after `forward`, restores its previous schema
+    pub rollback: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MigrationSettings {
     pub page_size: String,
@@ -95,4 +726,78 @@ pub struct MigrationResult {
     pub output_path: String,
     pub message: String,
     pub success: bool,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionProfile {
+    #[serde(default)]
+    pub id: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// SQLCipher settings in the same shape `connect_database` accepts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cipher_settings: Option<serde_json::Value>,
+    /// Profile id of the database this one is usually compared against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compare_partner: Option<String>,
+    /// Per-table key column overrides used for data comparison (table -> column)
+    #[serde(default)]
+    pub key_columns: HashMap<String, String>,
+    #[serde(default)]
+    pub access_mode: AccessMode,
+    #[serde(default)]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceState {
+    /// Profile ids that were connected when the workspace was saved
+    #[serde(default)]
+    pub open_profiles: Vec<String>,
+    /// Active comparison as (source profile id, target profile id)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceProfileResult {
+    pub profile: ConnectionProfile,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<DatabaseInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceOpenResult {
+    pub profiles: Vec<WorkspaceProfileResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseChangeKind {
+    /// Committed to by another connection or process
+    Modified,
+    /// File rewritten or swapped on disk; the connection was reopened
+    Replaced,
+    /// File no longer exists; the connection was closed
+    Deleted,
+}
+
+/// Payload of the `database-changed` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseChangeEvent {
+    pub path: String,
+    pub kind: DatabaseChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<DatabaseInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
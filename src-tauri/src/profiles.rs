@@ -0,0 +1,273 @@
+// src-tauri/src/profiles.rs
+use crate::license::get_app_data_dir;
+use crate::models::{ConnectionProfile, WorkspaceState};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PROFILES_FILE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProfilesFile {
+    version: u32,
+    #[serde(default)]
+    profiles: Vec<ConnectionProfile>,
+    #[serde(default)]
+    workspace: WorkspaceState,
+}
+
+impl Default for ProfilesFile {
+    fn default() -> Self {
+        Self {
+            version: PROFILES_FILE_VERSION,
+            profiles: Vec::new(),
+            workspace: WorkspaceState::default(),
+        }
+    }
+}
+
+/// Saved connection profiles and the last workspace, persisted as JSON in the app data dir
+pub struct ProfileManager {
+    profiles_file: PathBuf,
+    data: ProfilesFile,
+}
+
+impl ProfileManager {
+    pub fn new() -> Result<Self, String> {
+        let app_data_dir = get_app_data_dir()?;
+
+        fs::create_dir_all(&app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+        let profiles_file = app_data_dir.join("profiles.json");
+
+        let data = if profiles_file.exists() {
+            match Self::load(&profiles_file) {
+                Ok(data) => data,
+                Err(e) => {
+                    // Keep the broken file around instead of silently overwriting it
+                    println!("⚠️ {}. Starting with an empty profile list.", e);
+                    let _ = fs::rename(&profiles_file, profiles_file.with_extension("json.bak"));
+                    ProfilesFile::default()
+                }
+            }
+        } else {
+            ProfilesFile::default()
+        };
+
+        Ok(ProfileManager {
+            profiles_file,
+            data,
+        })
+    }
+
+    pub fn list_profiles(&self) -> Vec<ConnectionProfile> {
+        self.data.profiles.clone()
+    }
+
+    pub fn get_profile(&self, id: &str) -> Option<&ConnectionProfile> {
+        self.data.profiles.iter().find(|p| p.id == id)
+    }
+
+    pub fn find_by_path(&self, path: &str) -> Option<&ConnectionProfile> {
+        self.data.profiles.iter().find(|p| p.path == path)
+    }
+
+    /// Insert or update a profile. A profile without an id (or with an unknown id)
+    /// updates the existing profile for the same path, otherwise a new one is created.
+    /// A path already used by another profile is refused.
+    pub fn save_profile(
+        &mut self,
+        mut profile: ConnectionProfile,
+    ) -> Result<ConnectionProfile, String> {
+        if profile.path.trim().is_empty() {
+            return Err("Profile path is required".to_string());
+        }
+
+        if let Some(partner) = &profile.compare_partner {
+            if self.get_profile(partner).is_none() {
+                return Err(format!("Compare partner profile '{}' not found", partner));
+            }
+        }
+
+        let by_id = self
+            .data
+            .profiles
+            .iter()
+            .position(|p| !profile.id.is_empty() && p.id == profile.id);
+        let by_path = self
+            .data
+            .profiles
+            .iter()
+            .position(|p| p.path == profile.path);
+        let existing_idx = match (by_id, by_path) {
+            (Some(idx), Some(other)) if other != idx => {
+                let other = &self.data.profiles[other];
+                return Err(format!(
+                    "Profile '{}' already uses {}",
+                    other.alias.as_deref().unwrap_or(&other.id),
+                    profile.path
+                ));
+            }
+            (Some(idx), _) | (None, Some(idx)) => Some(idx),
+            (None, None) => None,
+        };
+
+        let mut data = self.data.clone();
+        let now = Utc::now();
+        match existing_idx {
+            Some(idx) => {
+                let existing = &mut data.profiles[idx];
+                profile.id = existing.id.clone();
+                profile.created_at = existing.created_at;
+                profile.updated_at = now;
+                *existing = profile.clone();
+            }
+            None => {
+                profile.id = generate_profile_id(&profile.path);
+                profile.created_at = now;
+                profile.updated_at = now;
+                data.profiles.push(profile.clone());
+            }
+        }
+
+        self.commit(data)?;
+        Ok(profile)
+    }
+
+    pub fn rename_profile(
+        &mut self,
+        id: &str,
+        alias: Option<String>,
+    ) -> Result<ConnectionProfile, String> {
+        let mut data = self.data.clone();
+        let profile = data
+            .profiles
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("Profile '{}' not found", id))?;
+
+        profile.alias = alias.filter(|a| !a.trim().is_empty());
+        profile.updated_at = Utc::now();
+        let updated = profile.clone();
+
+        self.commit(data)?;
+        Ok(updated)
+    }
+
+    pub fn delete_profile(&mut self, id: &str) -> Result<(), String> {
+        let mut data = self.data.clone();
+        let before = data.profiles.len();
+        data.profiles.retain(|p| p.id != id);
+        if data.profiles.len() == before {
+            return Err(format!("Profile '{}' not found", id));
+        }
+
+        // Drop dangling references to the deleted profile
+        for profile in &mut data.profiles {
+            if profile.compare_partner.as_deref() == Some(id) {
+                profile.compare_partner = None;
+            }
+        }
+        data.workspace.open_profiles.retain(|p| p != id);
+        if let Some((source, target)) = &data.workspace.comparison {
+            if source == id || target == id {
+                data.workspace.comparison = None;
+            }
+        }
+
+        self.commit(data)
+    }
+
+    pub fn set_key_columns(
+        &mut self,
+        id: &str,
+        key_columns: HashMap<String, String>,
+    ) -> Result<ConnectionProfile, String> {
+        let mut data = self.data.clone();
+        let profile = data
+            .profiles
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("Profile '{}' not found", id))?;
+
+        profile.key_columns = key_columns;
+        profile.updated_at = Utc::now();
+        let updated = profile.clone();
+
+        self.commit(data)?;
+        Ok(updated)
+    }
+
+    pub fn workspace(&self) -> WorkspaceState {
+        self.data.workspace.clone()
+    }
+
+    pub fn save_workspace(
+        &mut self,
+        mut workspace: WorkspaceState,
+    ) -> Result<WorkspaceState, String> {
+        for id in &workspace.open_profiles {
+            if self.get_profile(id).is_none() {
+                return Err(format!("Profile '{}' not found", id));
+            }
+        }
+        if let Some((source, target)) = &workspace.comparison {
+            if self.get_profile(source).is_none() || self.get_profile(target).is_none() {
+                return Err("Comparison refers to an unknown profile".to_string());
+            }
+        }
+
+        workspace.saved_at = Some(Utc::now());
+        let data = ProfilesFile {
+            workspace: workspace.clone(),
+            ..self.data.clone()
+        };
+        self.commit(data)?;
+        Ok(workspace)
+    }
+
+    fn load(path: &PathBuf) -> Result<ProfilesFile, String> {
+        let json =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read profiles file: {}", e))?;
+
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse profiles file: {}", e))
+    }
+
+    /// Write `data` to disk and only then make it the current state, so a failed write
+    /// leaves memory matching profiles.json
+    fn commit(&mut self, data: ProfilesFile) -> Result<(), String> {
+        Self::persist(&self.profiles_file, &data)?;
+        self.data = data;
+        Ok(())
+    }
+
+    fn persist(profiles_file: &Path, data: &ProfilesFile) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(data)
+            .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+
+        // Write to a temp file first so a crash never leaves a truncated profiles.json
+        let tmp_file = profiles_file.with_extension("json.tmp");
+        fs::write(&tmp_file, json).map_err(|e| format!("Failed to save profiles: {}", e))?;
+        fs::rename(&tmp_file, profiles_file)
+            .map_err(|e| format!("Failed to save profiles: {}", e))?;
+
+        Ok(())
+    }
+}
+
+fn generate_profile_id(path: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(path.as_bytes());
+    hasher.update(
+        Utc::now()
+            .timestamp_nanos_opt()
+            .unwrap_or_default()
+            .to_le_bytes(),
+    );
+    let result = hasher.finalize();
+    format!("{:x}", result)[..16].to_string()
+}
@@ -0,0 +1,513 @@
+// src-tauri/src/report.rs
+use crate::database::{typed_value_to_json, DatabaseManager};
+use crate::models::{
+    ComparisonReport, ReportFormat, ReportOptions, ReportResult, RowChangeKind, RowSample,
+    TableDataReport, TableDiff,
+};
+use crate::rowdiff::walk_rows;
+use anyhow::Context;
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use std::fmt::Write;
+use std::fs;
+
+/// Version of the JSON report layout
+pub const REPORT_FORMAT_VERSION: u32 = 1;
+
+const DEFAULT_MAX_SAMPLES: usize = 10;
+
+/// Compare two databases and write the result as a report. Written to `<output>.part`
+/// first, like exports, so a failed run never leaves half a report behind.
+pub fn generate_report(
+    manager: &DatabaseManager,
+    db1_path: &str,
+    db2_path: &str,
+    format: ReportFormat,
+    output_path: &str,
+    options: &ReportOptions,
+) -> anyhow::Result<ReportResult> {
+    let report = build_report(manager, db1_path, db2_path, options)?;
+    let content = match format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report)?,
+        ReportFormat::Markdown => render_markdown(&report),
+        ReportFormat::Html => render_html(&report),
+    };
+
+    let part_path = format!("{}.part", output_path);
+    let written = fs::write(&part_path, &content).and_then(|_| fs::rename(&part_path, output_path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&part_path);
+        return Err(e).with_context(|| format!("Failed to write {}", output_path));
+    }
+
+    let tables_with_differences = report
+        .tables
+        .iter()
+        .filter(|t| t.rows_inserted + t.rows_deleted + t.rows_modified > 0)
+        .count();
+    println!(
+        "📝 Wrote {:?} report for {} <-> {} to {}",
+        format, db1_path, db2_path, output_path
+    );
+    Ok(ReportResult {
+        file_path: output_path.to_string(),
+        format,
+        tables_compared: report.tables.len(),
+        tables_with_differences,
+        file_size: content.len() as u64,
+    })
+}
+
+pub fn build_report(
+    manager: &DatabaseManager,
+    db1_path: &str,
+    db2_path: &str,
+    options: &ReportOptions,
+) -> anyhow::Result<ComparisonReport> {
+    let schema = manager.compare_schemas(db1_path, db2_path, &options.filter, &options.rules)?;
+
+    let mut tables = Vec::new();
+    if !options.schema_only {
+        let conn1 = manager
+            .reader(db1_path)
+            .context("Database 1 not connected")?;
+        let conn2 = manager
+            .reader(db2_path)
+            .context("Database 2 not connected")?;
+
+        let mut common: Vec<&String> = schema
+            .identical_tables
+            .iter()
+            .chain(schema.modified_tables.iter().map(|t| &t.table_name))
+            .collect();
+        common.sort();
+        for table in common {
+            let table_report = compare_rows(manager, &conn1, &conn2, table, options)
+                .with_context(|| format!("Failed to compare rows of {}", table))?;
+            tables.push(table_report);
+        }
+    }
+
+    Ok(ComparisonReport {
+        format_version: REPORT_FORMAT_VERSION,
+        generated_at: chrono::Utc::now(),
+        database1: db1_path.to_string(),
+        database2: db2_path.to_string(),
+        schema,
+        tables,
+    })
+}
+
+/// Walk both tables in primary key order at once, counting and sampling differences
+fn compare_rows(
+    manager: &DatabaseManager,
+    conn1: &Connection,
+    conn2: &Connection,
+    table: &str,
+    options: &ReportOptions,
+) -> anyhow::Result<TableDataReport> {
+    let mut report = TableDataReport {
+        table_name: table.to_string(),
+        ..Default::default()
+    };
+    let columns1 = manager.get_table_columns(conn1, table)?;
+    let columns2 = manager.get_table_columns(conn2, table)?;
+
+    let key: Vec<String> = columns1
+        .iter()
+        .filter(|c| c.is_primary_key)
+        .map(|c| c.name.clone())
+        .collect();
+    if key.is_empty()
+        || !key
+            .iter()
+            .all(|k| columns2.iter().any(|c| c.is_primary_key && &c.name == k))
+    {
+        report.skipped = Some("No common primary key".to_string());
+        return Ok(report);
+    }
+    let columns: Vec<String> = columns1
+        .iter()
+        .filter(|c| columns2.iter().any(|t| t.name == c.name))
+        .filter(|c| !options.filter.ignores_column(table, &c.name))
+        .map(|c| c.name.clone())
+        .collect();
+    report.key_columns = key.clone();
+
+    let max_samples = options.max_samples.unwrap_or(DEFAULT_MAX_SAMPLES);
+    let sample = |kind, row1: Option<&[Value]>, row2: Option<&[Value]>, different| {
+        let any_row = row1.or(row2).unwrap_or_default();
+        RowSample {
+            kind,
+            key: any_row[..key.len()]
+                .iter()
+                .cloned()
+                .map(typed_value_to_json)
+                .collect(),
+            row_db1: row1.map(|r| row_map(&columns, &r[key.len()..])),
+            row_db2: row2.map(|r| row_map(&columns, &r[key.len()..])),
+            different_columns: different,
+        }
+    };
+
+    let visit = |row1: Option<&[Value]>, row2: Option<&[Value]>| {
+        match (row1, row2) {
+            (Some(_), None) => {
+                report.rows_db1 += 1;
+                report.rows_deleted += 1;
+                if report.samples.len() < max_samples {
+                    let s = sample(RowChangeKind::Deleted, row1, None, Vec::new());
+                    report.samples.push(s);
+                }
+            }
+            (None, Some(_)) => {
+                report.rows_db2 += 1;
+                report.rows_inserted += 1;
+                if report.samples.len() < max_samples {
+                    let s = sample(RowChangeKind::Inserted, None, row2, Vec::new());
+                    report.samples.push(s);
+                }
+            }
+            (Some(r1), Some(r2)) => {
+                report.rows_db1 += 1;
+                report.rows_db2 += 1;
+                let different: Vec<String> = columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| {
+                        let i = key.len() + i;
+                        !options.rules.values_equal(&r1[i], &r2[i])
+                    })
+                    .map(|(_, c)| c.clone())
+                    .collect();
+                if !different.is_empty() {
+                    report.rows_modified += 1;
+                    if report.samples.len() < max_samples {
+                        let s = sample(RowChangeKind::Modified, Some(r1), Some(r2), different);
+                        report.samples.push(s);
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    };
+    walk_rows(conn1, conn2, table, &key, [&columns, &columns], None, visit)?;
+    Ok(report)
+}
+
+fn row_map(columns: &[String], values: &[Value]) -> serde_json::Map<String, serde_json::Value> {
+    columns
+        .iter()
+        .zip(values)
+        .map(|(c, v)| (c.clone(), typed_value_to_json(v.clone())))
+        .collect()
+}
+
+/// One line per schema change of a modified table
+fn table_changes(diff: &TableDiff) -> Vec<String> {
+    let mut changes = Vec::new();
+    for column in &diff.removed_columns {
+        changes.push(format!("column `{}` only in database 1", column));
+    }
+    for column in &diff.added_columns {
+        changes.push(format!(
+            "column `{}` ({}) only in database 2",
+            column.name, column.data_type
+        ));
+    }
+    for column in &diff.modified_columns {
+        changes.push(format!(
+            "column `{}`: {}",
+            column.column_name,
+            column.changes.join(", ")
+        ));
+    }
+    if diff.column_order_changed {
+        changes.push("column order changed".to_string());
+    }
+    for change in &diff.option_changes {
+        changes.push(format!("table option {}", change));
+    }
+    changes
+}
+
+/// What changed in a sampled row: `col: old → new` for modified rows, the row itself otherwise
+fn sample_details(sample: &RowSample) -> String {
+    match (&sample.row_db1, &sample.row_db2) {
+        (Some(row1), Some(row2)) => sample
+            .different_columns
+            .iter()
+            .map(|c| format!("{}: {} → {}", c, row1[c], row2[c]))
+            .collect::<Vec<_>>()
+            .join("; "),
+        (Some(row), None) | (None, Some(row)) => serde_json::Value::Object(row.clone()).to_string(),
+        (None, None) => String::new(),
+    }
+}
+
+fn sample_key(sample: &RowSample) -> String {
+    sample
+        .key
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn kind_label(kind: RowChangeKind) -> &'static str {
+    match kind {
+        RowChangeKind::Inserted => "inserted",
+        RowChangeKind::Deleted => "deleted",
+        RowChangeKind::Modified => "modified",
+    }
+}
+
+fn render_markdown(report: &ComparisonReport) -> String {
+    let schema = &report.schema;
+    let mut md = String::new();
+    let _ = writeln!(md, "# Database comparison report\n");
+    let _ = writeln!(md, "- **Database 1:** `{}`", report.database1);
+    let _ = writeln!(md, "- **Database 2:** `{}`", report.database2);
+    let _ = writeln!(
+        md,
+        "- **Generated:** {} UTC\n",
+        report.generated_at.format("%Y-%m-%d %H:%M:%S")
+    );
+
+    let _ = writeln!(md, "## Schema\n");
+    let _ = writeln!(md, "| Tables | Count |\n|---|---:|");
+    let _ = writeln!(md, "| Only in database 2 | {} |", schema.added_tables.len());
+    let _ = writeln!(
+        md,
+        "| Only in database 1 | {} |",
+        schema.removed_tables.len()
+    );
+    let _ = writeln!(md, "| Modified | {} |", schema.modified_tables.len());
+    let _ = writeln!(md, "| Identical | {} |\n", schema.identical_tables.len());
+    for (title, tables) in [
+        ("Only in database 2", &schema.added_tables),
+        ("Only in database 1", &schema.removed_tables),
+    ] {
+        if !tables.is_empty() {
+            let _ = writeln!(md, "### {}\n", title);
+            for table in tables {
+                let _ = writeln!(md, "- `{}`", table);
+            }
+            md.push('\n');
+        }
+    }
+    for diff in &schema.modified_tables {
+        let _ = writeln!(md, "### Modified: `{}`\n", diff.table_name);
+        for change in table_changes(diff) {
+            let _ = writeln!(md, "- {}", change);
+        }
+        md.push('\n');
+    }
+
+    if !report.tables.is_empty() {
+        let _ = writeln!(md, "## Rows\n");
+        let _ = writeln!(
+            md,
+            "| Table | Database 1 | Database 2 | Inserted | Deleted | Modified |\n|---|---:|---:|---:|---:|---:|"
+        );
+        for table in &report.tables {
+            match &table.skipped {
+                Some(reason) => {
+                    let _ = writeln!(
+                        md,
+                        "| `{}` | | | | | skipped: {} |",
+                        table.table_name, reason
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        md,
+                        "| `{}` | {} | {} | {} | {} | {} |",
+                        table.table_name,
+                        table.rows_db1,
+                        table.rows_db2,
+                        table.rows_inserted,
+                        table.rows_deleted,
+                        table.rows_modified
+                    );
+                }
+            }
+        }
+        md.push('\n');
+
+        for table in report.tables.iter().filter(|t| !t.samples.is_empty()) {
+            let _ = writeln!(md, "### Sample differences: `{}`\n", table.table_name);
+            let _ = writeln!(md, "| Change | Key | Details |\n|---|---|---|");
+            for sample in &table.samples {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} |",
+                    kind_label(sample.kind),
+                    md_cell(&sample_key(sample)),
+                    md_cell(&sample_details(sample))
+                );
+            }
+            md.push('\n');
+        }
+    }
+    md
+}
+
+/// Table cells can't hold pipes or line breaks
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn render_html(report: &ComparisonReport) -> String {
+    let schema = &report.schema;
+    let mut html = String::new();
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">"
+    );
+    let _ = writeln!(html, "<title>Database comparison report</title>");
+    html.push_str(
+        "<style>\n\
+         body { font-family: system-ui, sans-serif; margin: 2rem; color: #1f2328; }\n\
+         table { border-collapse: collapse; margin: 0.5rem 0 1.5rem; }\n\
+         th, td { border: 1px solid #d0d7de; padding: 4px 8px; text-align: left; vertical-align: top; }\n\
+         th { background: #f6f8fa; }\n\
+         td.num { text-align: right; }\n\
+         code { background: #f6f8fa; padding: 0 3px; }\n\
+         .inserted { color: #1a7f37; } .deleted { color: #cf222e; } .modified { color: #9a6700; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+
+    let _ = writeln!(html, "<h1>Database comparison report</h1>\n<ul>");
+    let _ = writeln!(
+        html,
+        "<li><b>Database 1:</b> <code>{}</code></li>",
+        escape_html(&report.database1)
+    );
+    let _ = writeln!(
+        html,
+        "<li><b>Database 2:</b> <code>{}</code></li>",
+        escape_html(&report.database2)
+    );
+    let _ = writeln!(
+        html,
+        "<li><b>Generated:</b> {} UTC</li>\n</ul>",
+        report.generated_at.format("%Y-%m-%d %H:%M:%S")
+    );
+
+    let _ = writeln!(
+        html,
+        "<h2>Schema</h2>\n<table>\n<tr><th>Tables</th><th>Count</th></tr>"
+    );
+    for (label, count) in [
+        ("Only in database 2", schema.added_tables.len()),
+        ("Only in database 1", schema.removed_tables.len()),
+        ("Modified", schema.modified_tables.len()),
+        ("Identical", schema.identical_tables.len()),
+    ] {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"num\">{}</td></tr>",
+            label, count
+        );
+    }
+    html.push_str("</table>\n");
+    for (title, tables) in [
+        ("Only in database 2", &schema.added_tables),
+        ("Only in database 1", &schema.removed_tables),
+    ] {
+        if !tables.is_empty() {
+            let _ = writeln!(html, "<h3>{}</h3>\n<ul>", title);
+            for table in tables {
+                let _ = writeln!(html, "<li><code>{}</code></li>", escape_html(table));
+            }
+            html.push_str("</ul>\n");
+        }
+    }
+    for diff in &schema.modified_tables {
+        let _ = writeln!(
+            html,
+            "<h3>Modified: <code>{}</code></h3>\n<ul>",
+            escape_html(&diff.table_name)
+        );
+        for change in table_changes(diff) {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(&change));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if !report.tables.is_empty() {
+        let _ = writeln!(
+            html,
+            "<h2>Rows</h2>\n<table>\n<tr><th>Table</th><th>Database 1</th><th>Database 2</th>\
+             <th>Inserted</th><th>Deleted</th><th>Modified</th></tr>"
+        );
+        for table in &report.tables {
+            let name = escape_html(&table.table_name);
+            match &table.skipped {
+                Some(reason) => {
+                    let _ = writeln!(
+                        html,
+                        "<tr><td><code>{}</code></td><td colspan=\"5\">skipped: {}</td></tr>",
+                        name,
+                        escape_html(reason)
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        html,
+                        "<tr><td><code>{}</code></td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                         <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                        name,
+                        table.rows_db1,
+                        table.rows_db2,
+                        table.rows_inserted,
+                        table.rows_deleted,
+                        table.rows_modified
+                    );
+                }
+            }
+        }
+        html.push_str("</table>\n");
+
+        for table in report.tables.iter().filter(|t| !t.samples.is_empty()) {
+            let _ = writeln!(
+                html,
+                "<h3>Sample differences: <code>{}</code></h3>\n<table>\n\
+                 <tr><th>Change</th><th>Key</th><th>Details</th></tr>",
+                escape_html(&table.table_name)
+            );
+            for sample in &table.samples {
+                let kind = kind_label(sample.kind);
+                let _ = writeln!(
+                    html,
+                    "<tr><td class=\"{}\">{}</td><td><code>{}</code></td><td><code>{}</code></td></tr>",
+                    kind,
+                    kind,
+                    escape_html(&sample_key(sample)),
+                    escape_html(&sample_details(sample))
+                );
+            }
+            html.push_str("</table>\n");
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
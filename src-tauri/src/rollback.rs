@@ -0,0 +1,173 @@
+// src-tauri/src/rollback.rs
+use crate::browse::quote_identifier;
+use crate::database::json_to_value;
+use crate::dump::sql_literal;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Writes the script that undoes a data patch while the patch is generated. Rows the
+/// patch deletes or updates are read from the database it will be applied to, so the
+/// rollback restores their exact values as of generation time.
+pub struct RollbackWriter<'a, W: Write> {
+    conn: &'a Connection,
+    out: W,
+    /// Insertable (non-generated) columns per table
+    columns: HashMap<String, Vec<String>>,
+    pub statements: u64,
+}
+
+impl<'a, W: Write> RollbackWriter<'a, W> {
+    /// `conn` reads the database the forward patch applies to
+    pub fn new(
+        conn: &'a Connection,
+        mut out: W,
+        forward_patch: &str,
+        apply_to: &str,
+    ) -> anyhow::Result<Self> {
+        writeln!(out, "-- Data Rollback Patch")?;
+        writeln!(out, "-- Undoes: {}", forward_patch)?;
+        writeln!(out, "-- Apply to: {} after the forward patch", apply_to)?;
+        writeln!(out, "-- Generated: {} UTC", chrono::Utc::now())?;
+        writeln!(out)?;
+        writeln!(out, "BEGIN TRANSACTION;\n")?;
+        Ok(Self {
+            conn,
+            out,
+            columns: HashMap::new(),
+            statements: 0,
+        })
+    }
+
+    /// The patch inserts this row; the rollback deletes it again
+    pub fn undo_insert(
+        &mut self,
+        table: &str,
+        key_column: &str,
+        key: &serde_json::Value,
+    ) -> anyhow::Result<()> {
+        writeln!(
+            self.out,
+            "DELETE FROM {} WHERE {} = {};",
+            quote_identifier(table),
+            quote_identifier(key_column),
+            sql_literal(&json_to_value(key)?)
+        )?;
+        self.statements += 1;
+        Ok(())
+    }
+
+    /// The patch deletes this row; the rollback inserts it back as it is now
+    pub fn undo_delete(
+        &mut self,
+        table: &str,
+        key_column: &str,
+        key: &serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let columns = self.insertable_columns(table)?;
+        let Some(values) = self.current_values(table, key_column, key, &columns)? else {
+            return self.not_found(table, key);
+        };
+        let names: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+        let values: Vec<String> = values.iter().map(sql_literal).collect();
+        writeln!(
+            self.out,
+            "INSERT INTO {} ({}) VALUES ({});",
+            quote_identifier(table),
+            names.join(", "),
+            values.join(", ")
+        )?;
+        self.statements += 1;
+        Ok(())
+    }
+
+    /// The patch updates `columns` of this row; the rollback sets them back
+    pub fn undo_update(
+        &mut self,
+        table: &str,
+        key_column: &str,
+        key: &serde_json::Value,
+        columns: &[String],
+    ) -> anyhow::Result<()> {
+        let Some(values) = self.current_values(table, key_column, key, columns)? else {
+            return self.not_found(table, key);
+        };
+        let set_clauses: Vec<String> = columns
+            .iter()
+            .zip(&values)
+            .map(|(c, v)| format!("{} = {}", quote_identifier(c), sql_literal(v)))
+            .collect();
+        writeln!(
+            self.out,
+            "UPDATE {} SET {} WHERE {} = {};",
+            quote_identifier(table),
+            set_clauses.join(", "),
+            quote_identifier(key_column),
+            sql_literal(&json_to_value(key)?)
+        )?;
+        self.statements += 1;
+        Ok(())
+    }
+
+    pub fn comment(&mut self, text: &str) -> anyhow::Result<()> {
+        writeln!(self.out, "-- {}", text)?;
+        Ok(())
+    }
+
+    /// Close the script and hand back the writer
+    pub fn finish(mut self) -> anyhow::Result<W> {
+        writeln!(self.out, "COMMIT;")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// The database changed since the comparison; nothing to restore for this row
+    fn not_found(&mut self, table: &str, key: &serde_json::Value) -> anyhow::Result<()> {
+        writeln!(
+            self.out,
+            "-- {} row {} not found at generation time, nothing to restore",
+            table, key
+        )?;
+        Ok(())
+    }
+
+    fn insertable_columns(&mut self, table: &str) -> anyhow::Result<Vec<String>> {
+        if let Some(columns) = self.columns.get(table) {
+            return Ok(columns.clone());
+        }
+        let columns: Vec<String> = self
+            .conn
+            .prepare("SELECT name FROM pragma_table_xinfo(?1) WHERE hidden = 0")?
+            .query_map([table], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        if columns.is_empty() {
+            anyhow::bail!("Table '{}' not found", table);
+        }
+        self.columns.insert(table.to_string(), columns.clone());
+        Ok(columns)
+    }
+
+    fn current_values(
+        &self,
+        table: &str,
+        key_column: &str,
+        key: &serde_json::Value,
+        columns: &[String],
+    ) -> anyhow::Result<Option<Vec<Value>>> {
+        let select: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+        let sql = format!(
+            "SELECT {} FROM {} WHERE {} = ?1",
+            select.join(", "),
+            quote_identifier(table),
+            quote_identifier(key_column)
+        );
+        let values = self
+            .conn
+            .query_row(&sql, [json_to_value(key)?], |row| {
+                (0..columns.len()).map(|i| row.get::<_, Value>(i)).collect()
+            })
+            .optional()?;
+        Ok(values)
+    }
+}
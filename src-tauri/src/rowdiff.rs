@@ -0,0 +1,204 @@
+// src-tauri/src/rowdiff.rs
+use crate::browse::quote_identifier;
+use crate::database::{value_to_json, DatabaseManager};
+use crate::merge::compare_keys;
+use crate::models::{ComparisonFilter, ComparisonRules, DifferentRow, TableRowComparison};
+use anyhow::{bail, Context};
+use rusqlite::types::Value;
+use rusqlite::{Connection, Rows};
+use std::cmp::Ordering;
+
+/// Walk two tables in key order at once. `visit` gets the row of each key from both
+/// sides (key values first, then the side's `columns`), with `None` for a side that
+/// doesn't have the key.
+pub(crate) fn walk_rows(
+    conn1: &Connection,
+    conn2: &Connection,
+    table: &str,
+    key: &[String],
+    columns: [&[String]; 2],
+    limit: Option<i64>,
+    mut visit: impl FnMut(Option<&[Value]>, Option<&[Value]>) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let select = |columns: &[String]| {
+        let mut sql = format!(
+            "SELECT {} FROM {} ORDER BY {}",
+            key.iter()
+                .chain(columns)
+                .map(|c| quote_identifier(c))
+                .collect::<Vec<_>>()
+                .join(", "),
+            quote_identifier(table),
+            key.iter()
+                .map(|k| format!("{} COLLATE BINARY", quote_identifier(k)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        sql
+    };
+    let mut stmt1 = conn1.prepare(&select(columns[0]))?;
+    let mut stmt2 = conn2.prepare(&select(columns[1]))?;
+    let mut rows1 = stmt1.query([])?;
+    let mut rows2 = stmt2.query([])?;
+    let width1 = key.len() + columns[0].len();
+    let width2 = key.len() + columns[1].len();
+
+    let mut row1 = next_row(&mut rows1, width1)?;
+    let mut row2 = next_row(&mut rows2, width2)?;
+    loop {
+        let ordering = match (&row1, &row2) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(r1), Some(r2)) => compare_keys(&r1[..key.len()], &r2[..key.len()]),
+        };
+        match ordering {
+            Ordering::Less => {
+                visit(row1.as_deref(), None)?;
+                row1 = next_row(&mut rows1, width1)?;
+            }
+            Ordering::Greater => {
+                visit(None, row2.as_deref())?;
+                row2 = next_row(&mut rows2, width2)?;
+            }
+            Ordering::Equal => {
+                visit(row1.as_deref(), row2.as_deref())?;
+                row1 = next_row(&mut rows1, width1)?;
+                row2 = next_row(&mut rows2, width2)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compare the rows of a table matched on `key_column`, for the data comparison view.
+/// Rows with a NULL key can't be matched and are left out. Columns the filter ignores
+/// are still returned but never make a row different.
+pub fn compare_table_rows(
+    manager: &DatabaseManager,
+    db1_path: &str,
+    db2_path: &str,
+    table: &str,
+    key_column: &str,
+    filter: &ComparisonFilter,
+    rules: &ComparisonRules,
+    limit: Option<i64>,
+) -> anyhow::Result<TableRowComparison> {
+    if !filter.includes_table(table) {
+        bail!("Table '{}' is excluded by the comparison filter", table);
+    }
+
+    let conn1 = manager
+        .reader(db1_path)
+        .context("Database 1 not connected")?;
+    let conn2 = manager
+        .reader(db2_path)
+        .context("Database 2 not connected")?;
+    let names = |conn: &Connection| -> anyhow::Result<Vec<String>> {
+        Ok(manager
+            .get_table_columns(conn, table)?
+            .into_iter()
+            .map(|c| c.name)
+            .collect())
+    };
+    let (columns1, columns2) = (names(&conn1)?, names(&conn2)?);
+    let key = [key_column.to_string()];
+    if !columns1.contains(&key[0]) || !columns2.contains(&key[0]) {
+        bail!("Invalid key column ({}) for table {}", key_column, table);
+    }
+
+    let mut result = TableRowComparison {
+        common_columns: columns1
+            .iter()
+            .filter(|c| columns2.contains(c))
+            .cloned()
+            .collect(),
+        source_only_columns: columns1
+            .iter()
+            .filter(|c| !columns2.contains(c))
+            .cloned()
+            .collect(),
+        target_only_columns: columns2
+            .iter()
+            .filter(|c| !columns1.contains(c))
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+    // Compared columns with their position in each side's rows (after the key)
+    let compared: Vec<(&String, usize, usize)> = result
+        .common_columns
+        .iter()
+        .filter(|c| *c != key_column && !filter.ignores_column(table, c))
+        .filter_map(|c| {
+            let i1 = columns1.iter().position(|n| n == c)?;
+            let i2 = columns2.iter().position(|n| n == c)?;
+            Some((c, 1 + i1, 1 + i2))
+        })
+        .collect();
+
+    let (mut identical, mut different, mut missing, mut extra) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    walk_rows(
+        &conn1,
+        &conn2,
+        table,
+        &key,
+        [&columns1, &columns2],
+        limit,
+        |row1, row2| {
+            match (row1, row2) {
+                (Some(r1), _) if r1[0] == Value::Null => {}
+                (_, Some(r2)) if r2[0] == Value::Null => {}
+                (Some(r1), Some(r2)) => {
+                    let columns: Vec<String> = compared
+                        .iter()
+                        .filter(|(_, i1, i2)| !rules.values_equal(&r1[*i1], &r2[*i2]))
+                        .map(|(c, _, _)| c.to_string())
+                        .collect();
+                    if columns.is_empty() {
+                        identical.push(row_map(&columns1, &r1[1..]));
+                    } else {
+                        different.push(DifferentRow {
+                            source_row: row_map(&columns1, &r1[1..]),
+                            target_row: row_map(&columns2, &r2[1..]),
+                            different_columns: columns,
+                        });
+                    }
+                }
+                (Some(r1), None) => missing.push(row_map(&columns1, &r1[1..])),
+                (None, Some(r2)) => extra.push(row_map(&columns2, &r2[1..])),
+                (None, None) => {}
+            }
+            Ok(())
+        },
+    )?;
+
+    result.identical_rows = identical;
+    result.different_rows = different;
+    result.missing_in_target = missing;
+    result.extra_in_target = extra;
+    Ok(result)
+}
+
+fn next_row(rows: &mut Rows, width: usize) -> anyhow::Result<Option<Vec<Value>>> {
+    let Some(row) = rows.next()? else {
+        return Ok(None);
+    };
+    let values = (0..width)
+        .map(|i| row.get::<_, Value>(i))
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(Some(values))
+}
+
+/// Same encoding as `get_table_data`, which the data patch generator reads
+fn row_map(columns: &[String], values: &[Value]) -> serde_json::Map<String, serde_json::Value> {
+    columns
+        .iter()
+        .zip(values)
+        .map(|(c, v)| (c.clone(), value_to_json(v.clone())))
+        .collect()
+}
@@ -0,0 +1,138 @@
+// src-tauri/src/rules.rs
+use crate::importer::Affinity;
+use crate::models::ComparisonRules;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use rusqlite::types::Value;
+
+/// Text layouts tried (after RFC 3339) when timestamps are normalised
+const TIMESTAMP_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// What the rules look at in a cell, from either a SQLite value or its JSON form
+#[derive(Clone, Copy)]
+enum Cell<'a> {
+    Null,
+    Integer(i64),
+    Number(f64),
+    Text(&'a str),
+    Blob(&'a [u8]),
+    /// JSON arrays and objects, which only equal themselves
+    Json(&'a serde_json::Value),
+}
+
+impl ComparisonRules {
+    pub fn types_equal(&self, a: &str, b: &str) -> bool {
+        if self.type_affinity {
+            Affinity::of(a) == Affinity::of(b)
+        } else {
+            a.eq_ignore_ascii_case(b)
+        }
+    }
+
+    pub fn values_equal(&self, a: &Value, b: &Value) -> bool {
+        self.cells_equal(value_cell(a), value_cell(b))
+    }
+
+    /// Same as `values_equal`, for rows the frontend sends as JSON
+    pub fn json_values_equal(&self, a: &serde_json::Value, b: &serde_json::Value) -> bool {
+        self.cells_equal(json_cell(a), json_cell(b))
+    }
+
+    fn cells_equal(&self, a: Cell, b: Cell) -> bool {
+        let tolerance = self.numeric_tolerance.unwrap_or(0.0);
+        match (a, b) {
+            (Cell::Null, Cell::Null) => true,
+            // Exact for integers unless a tolerance asks otherwise (f64 loses precision)
+            (Cell::Integer(a), Cell::Integer(b)) if tolerance == 0.0 => a == b,
+            (Cell::Integer(_) | Cell::Number(_), Cell::Integer(_) | Cell::Number(_)) => {
+                (as_f64(a) - as_f64(b)).abs() <= tolerance
+            }
+            (Cell::Text(a), Cell::Text(b)) => self.texts_equal(a, b),
+            (Cell::Blob(a), Cell::Blob(b)) => a == b,
+            (Cell::Json(a), Cell::Json(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    fn texts_equal(&self, a: &str, b: &str) -> bool {
+        let (a, b) = if self.trim_whitespace {
+            (a.trim(), b.trim())
+        } else {
+            (a, b)
+        };
+        if self.normalize_timestamps {
+            if let (Some(a), Some(b)) = (parse_timestamp(a), parse_timestamp(b)) {
+                return a == b;
+            }
+        }
+        if self.case_insensitive {
+            a.to_lowercase() == b.to_lowercase()
+        } else {
+            a == b
+        }
+    }
+}
+
+fn as_f64(cell: Cell) -> f64 {
+    match cell {
+        Cell::Integer(i) => i as f64,
+        Cell::Number(f) => f,
+        _ => 0.0,
+    }
+}
+
+fn value_cell(value: &Value) -> Cell<'_> {
+    match value {
+        Value::Null => Cell::Null,
+        Value::Integer(i) => Cell::Integer(*i),
+        Value::Real(f) => Cell::Number(*f),
+        Value::Text(s) => Cell::Text(s),
+        Value::Blob(b) => Cell::Blob(b),
+    }
+}
+
+fn json_cell(value: &serde_json::Value) -> Cell<'_> {
+    match value {
+        serde_json::Value::Null => Cell::Null,
+        serde_json::Value::Bool(b) => Cell::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Cell::Integer(i),
+            None => Cell::Number(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Cell::Text(s),
+        other => Cell::Json(other),
+    }
+}
+
+/// Order of two timestamps as instants, or `None` unless both are text that parses
+pub(crate) fn compare_timestamps(a: &Value, b: &Value) -> Option<std::cmp::Ordering> {
+    match (a, b) {
+        (Value::Text(a), Value::Text(b)) => {
+            Some(parse_timestamp(a.trim())?.cmp(&parse_timestamp(b.trim())?))
+        }
+        _ => None,
+    }
+}
+
+/// UTC instant of a timestamp in any of the common layouts; offsets are applied
+fn parse_timestamp(text: &str) -> Option<NaiveDateTime> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Some(dt.naive_utc());
+    }
+    // `2024-01-01 10:00:00+02:00`: RFC 3339 with a space
+    if let Ok(dt) = DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%:z") {
+        return Some(dt.naive_utc());
+    }
+    TIMESTAMP_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+}
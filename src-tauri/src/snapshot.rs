@@ -0,0 +1,286 @@
+// src-tauri/src/snapshot.rs
+use crate::browse::quote_identifier;
+use crate::database::{hex, typed_value_to_json, DatabaseManager};
+use crate::models::{DataComparisonResult, SchemaSnapshot, SnapshotResult, TableRowHashes};
+use anyhow::{bail, Context};
+use rusqlite::types::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Version of the snapshot file layout; newer snapshots are refused
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Snapshots are told apart from database files by their `.json` extension
+pub fn is_snapshot_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Save the schema of a connected database, with row hashes of every table if asked
+pub fn save_snapshot(
+    manager: &DatabaseManager,
+    db_path: &str,
+    output_path: &str,
+    include_row_hashes: bool,
+) -> anyhow::Result<SnapshotResult> {
+    let tables = manager.get_tables(db_path)?;
+
+    let mut row_hashes = HashMap::new();
+    if include_row_hashes {
+        for table in &tables {
+            let hashes = hash_table(manager, db_path, &table.name, None)
+                .with_context(|| format!("Failed to hash rows of {}", table.name))?;
+            row_hashes.insert(table.name.clone(), hashes);
+        }
+    }
+    let rows_hashed = row_hashes.values().map(|h| h.row_count).sum();
+
+    let snapshot = SchemaSnapshot {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        created_at: chrono::Utc::now(),
+        database_path: db_path.to_string(),
+        tables,
+        row_hashes,
+    };
+    let content = serde_json::to_string_pretty(&snapshot)?;
+    let part_path = format!("{}.part", output_path);
+    let written = fs::write(&part_path, &content).and_then(|_| fs::rename(&part_path, output_path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&part_path);
+        return Err(e).with_context(|| format!("Failed to write {}", output_path));
+    }
+
+    println!(
+        "📸 Saved snapshot of {} to {} ({} tables, {} rows hashed)",
+        db_path,
+        output_path,
+        snapshot.tables.len(),
+        rows_hashed
+    );
+    Ok(SnapshotResult {
+        file_path: output_path.to_string(),
+        tables: snapshot.tables.len(),
+        rows_hashed,
+        file_size: content.len() as u64,
+    })
+}
+
+pub fn load_snapshot(path: &str) -> anyhow::Result<SchemaSnapshot> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read snapshot {}", path))?;
+    let snapshot: SchemaSnapshot = serde_json::from_str(&content)
+        .with_context(|| format!("{} is not a schema snapshot", path))?;
+    if snapshot.format_version > SNAPSHOT_FORMAT_VERSION {
+        bail!(
+            "Snapshot {} was saved by a newer version (format {})",
+            path,
+            snapshot.format_version
+        );
+    }
+    Ok(snapshot)
+}
+
+/// Compare a table's rows where one or both sides are snapshots. Live sides are hashed
+/// over the snapshot's columns, so only rows with a different hash (or key) count.
+pub fn compare_table_data(
+    manager: &DatabaseManager,
+    db1_path: &str,
+    db1_is_snapshot: bool,
+    db2_path: &str,
+    db2_is_snapshot: bool,
+    table_name: &str,
+) -> anyhow::Result<DataComparisonResult> {
+    let stored = |path: &str, is_snapshot: bool| -> anyhow::Result<Option<TableRowHashes>> {
+        if !is_snapshot {
+            return Ok(None);
+        }
+        let mut snapshot = load_snapshot(path)?;
+        match snapshot.row_hashes.remove(table_name) {
+            Some(hashes) => Ok(Some(hashes)),
+            None => bail!(
+                "Snapshot {} has no row hashes for table '{}'",
+                path,
+                table_name
+            ),
+        }
+    };
+    let stored1 = stored(db1_path, db1_is_snapshot)?;
+    let stored2 = stored(db2_path, db2_is_snapshot)?;
+    let columns = stored1
+        .as_ref()
+        .or(stored2.as_ref())
+        .map(|h| h.columns.clone());
+
+    let hashes1 = match stored1 {
+        Some(hashes) => hashes,
+        None => hash_table(manager, db1_path, table_name, columns.as_deref())?,
+    };
+    let hashes2 = match stored2 {
+        Some(hashes) => hashes,
+        None => hash_table(manager, db2_path, table_name, columns.as_deref())?,
+    };
+    if hashes1.columns != hashes2.columns || hashes1.key_columns != hashes2.key_columns {
+        bail!(
+            "Table '{}' has different columns or primary key on each side - compare schemas first",
+            table_name
+        );
+    }
+
+    let identical = hashes1.table_hash == hashes2.table_hash;
+    let (inserted, deleted, modified) = if hashes1.key_columns.is_empty() {
+        // Without a key only the whole table can be compared
+        let modified = if identical {
+            0
+        } else {
+            hashes1.row_count.min(hashes2.row_count)
+        };
+        (0, 0, modified as i64)
+    } else {
+        let deleted = hashes1
+            .rows
+            .keys()
+            .filter(|k| !hashes2.rows.contains_key(*k))
+            .count();
+        let inserted = hashes2
+            .rows
+            .keys()
+            .filter(|k| !hashes1.rows.contains_key(*k))
+            .count();
+        let modified = hashes1
+            .rows
+            .iter()
+            .filter(|(k, hash)| hashes2.rows.get(*k).is_some_and(|h| h != *hash))
+            .count();
+        (inserted as i64, deleted as i64, modified as i64)
+    };
+
+    Ok(DataComparisonResult {
+        table_name: table_name.to_string(),
+        total_rows_db1: hashes1.row_count as i64,
+        total_rows_db2: hashes2.row_count as i64,
+        rows_inserted: inserted,
+        rows_deleted: deleted,
+        rows_potentially_modified: modified,
+        identical,
+    })
+}
+
+/// Hash every row of a table of a connected database, in primary key order. `columns`
+/// limits (and orders) the hashed columns; by default all columns are hashed.
+fn hash_table(
+    manager: &DatabaseManager,
+    db_path: &str,
+    table_name: &str,
+    columns: Option<&[String]>,
+) -> anyhow::Result<TableRowHashes> {
+    let conn = manager.reader(db_path)?;
+    let table_columns = manager.get_table_columns(&conn, table_name)?;
+    if table_columns.is_empty() {
+        bail!("Table '{}' not found in {}", table_name, db_path);
+    }
+    let columns: Vec<String> = match columns {
+        Some(columns) => {
+            if let Some(missing) = columns
+                .iter()
+                .find(|c| !table_columns.iter().any(|t| &t.name == *c))
+            {
+                bail!("Column '{}' not found in {}", missing, table_name);
+            }
+            columns.to_vec()
+        }
+        None => table_columns.iter().map(|c| c.name.clone()).collect(),
+    };
+    let key_columns: Vec<String> = table_columns
+        .iter()
+        .filter(|c| c.is_primary_key)
+        .map(|c| c.name.clone())
+        .collect();
+
+    // Keyless tables are ordered by every column so the table hash is still stable
+    let order_by = if key_columns.is_empty() {
+        &columns
+    } else {
+        &key_columns
+    };
+    let sql = format!(
+        "SELECT {} FROM {} ORDER BY {}",
+        key_columns
+            .iter()
+            .chain(&columns)
+            .map(|c| quote_identifier(c))
+            .collect::<Vec<_>>()
+            .join(", "),
+        quote_identifier(table_name),
+        order_by
+            .iter()
+            .map(|c| format!("{} COLLATE BINARY", quote_identifier(c)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let mut hashes = TableRowHashes {
+        key_columns,
+        columns,
+        ..Default::default()
+    };
+    let key_len = hashes.key_columns.len();
+    let mut table_hasher = Sha256::new();
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let values = (0..key_len + hashes.columns.len())
+            .map(|i| row.get::<_, Value>(i))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut row_hasher = Sha256::new();
+        for value in &values[key_len..] {
+            hash_value(&mut row_hasher, value);
+        }
+        // 128 bits per row is plenty and keeps snapshots small
+        let row_hash = hex(&row_hasher.finalize()[..16]);
+        table_hasher.update(row_hash.as_bytes());
+
+        if key_len > 0 {
+            let key: Vec<serde_json::Value> = values[..key_len]
+                .iter()
+                .cloned()
+                .map(typed_value_to_json)
+                .collect();
+            let key = serde_json::Value::Array(key).to_string();
+            table_hasher.update(key.as_bytes());
+            hashes.rows.insert(key, row_hash);
+        }
+        hashes.row_count += 1;
+    }
+    hashes.table_hash = hex(&table_hasher.finalize());
+    Ok(hashes)
+}
+
+/// Feed a value to the hasher with its storage class and length, so `1`, `1.0` and
+/// `'1'` (or `'ab','c'` and `'a','bc'`) never hash the same
+fn hash_value(hasher: &mut Sha256, value: &Value) {
+    match value {
+        Value::Null => hasher.update([0]),
+        Value::Integer(i) => {
+            hasher.update([1]);
+            hasher.update(i.to_be_bytes());
+        }
+        Value::Real(f) => {
+            hasher.update([2]);
+            hasher.update(f.to_bits().to_be_bytes());
+        }
+        Value::Text(s) => {
+            hasher.update([3]);
+            hasher.update((s.len() as u64).to_be_bytes());
+            hasher.update(s.as_bytes());
+        }
+        Value::Blob(b) => {
+            hasher.update([4]);
+            hasher.update((b.len() as u64).to_be_bytes());
+            hasher.update(b);
+        }
+    }
+}
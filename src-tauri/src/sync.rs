@@ -0,0 +1,396 @@
+// src-tauri/src/sync.rs
+use crate::browse::quote_identifier;
+use crate::database::DatabaseManager;
+use crate::dump::{read_schema_objects, sql_literal};
+use crate::merge::compare_keys;
+use crate::models::{
+    ComparisonFilter, ComparisonRules, SkippedTable, SyncConflictPolicy, SyncOptions, SyncResult,
+    SyncTableStats,
+};
+use crate::rules::compare_timestamps;
+use anyhow::Context;
+use rusqlite::types::Value;
+use rusqlite::{Connection, Rows};
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+/// Compare two databases row by row and write two complementary patches: rows only one
+/// side has are inserted into the other, and rows that differ are updated on the losing
+/// side according to the conflict policy. Nothing is ever deleted. Tables the filter
+/// excludes are left alone, and its ignored columns never make rows differ.
+pub fn sync(
+    manager: &DatabaseManager,
+    source_path: &str,
+    target_path: &str,
+    options: &SyncOptions,
+    filter: &ComparisonFilter,
+    source_patch_path: &str,
+    target_patch_path: &str,
+) -> anyhow::Result<SyncResult> {
+    let result = write_patches(
+        manager,
+        source_path,
+        target_path,
+        options,
+        filter,
+        source_patch_path,
+        target_patch_path,
+    );
+    if result.is_err() {
+        let _ = fs::remove_file(source_patch_path);
+        let _ = fs::remove_file(target_patch_path);
+    }
+    let (tables, skipped_tables) = result?;
+
+    println!(
+        "🔁 Synced {} <-> {}: {} tables ({:?})",
+        source_path,
+        target_path,
+        tables.len(),
+        options.policy
+    );
+    Ok(SyncResult {
+        source_patch_path: source_patch_path.to_string(),
+        target_patch_path: target_patch_path.to_string(),
+        policy: options.policy,
+        tables,
+        skipped_tables,
+    })
+}
+
+fn write_patches(
+    manager: &DatabaseManager,
+    source_path: &str,
+    target_path: &str,
+    options: &SyncOptions,
+    filter: &ComparisonFilter,
+    source_patch_path: &str,
+    target_patch_path: &str,
+) -> anyhow::Result<(Vec<SyncTableStats>, Vec<SkippedTable>)> {
+    let source = manager
+        .reader(source_path)
+        .context("Source database not connected")?;
+    let target = manager
+        .reader(target_path)
+        .context("Target database not connected")?;
+
+    let mut to_source = create_patch(source_patch_path)?;
+    let mut to_target = create_patch(target_patch_path)?;
+    write_header(
+        &mut to_source,
+        "Target → Source (Sync)",
+        &format!("{} (Source)", source_path),
+        options.policy,
+    )?;
+    write_header(
+        &mut to_target,
+        "Source → Target (Sync)",
+        &format!("{} (Target)", target_path),
+        options.policy,
+    )?;
+
+    let table_names = match &options.tables {
+        Some(tables) => tables.clone(),
+        None => {
+            let target_tables = read_schema_objects(&target)?.tables;
+            read_schema_objects(&source)?
+                .tables
+                .into_iter()
+                .map(|(name, _)| name)
+                .filter(|name| target_tables.iter().any(|(t, _)| t == name))
+                .collect()
+        }
+    };
+
+    let mut tables = Vec::new();
+    let mut skipped_tables = Vec::new();
+    for table in table_names.iter().filter(|t| filter.includes_table(t)) {
+        let source_columns = manager.get_table_columns(&source, table)?;
+        let target_columns = manager.get_table_columns(&target, table)?;
+        if source_columns.is_empty() || target_columns.is_empty() {
+            anyhow::bail!("Table '{}' not found in both databases", table);
+        }
+
+        let common: Vec<String> = source_columns
+            .iter()
+            .filter(|c| target_columns.iter().any(|t| t.name == c.name))
+            .map(|c| c.name.clone())
+            .collect();
+        let key: Vec<String> = match options.key_columns.get(table) {
+            Some(column) => vec![column.clone()],
+            None => source_columns
+                .iter()
+                .filter(|c| c.is_primary_key)
+                .map(|c| c.name.clone())
+                .collect(),
+        };
+        let updated_at = common.iter().position(|c| c == updated_at_column(options));
+        let skip_reason = if key.is_empty() || !key.iter().all(|k| common.contains(k)) {
+            Some("no key column on both sides".to_string())
+        } else if options.policy == SyncConflictPolicy::Newest && updated_at.is_none() {
+            Some(format!(
+                "no '{}' column on both sides for the newest policy",
+                updated_at_column(options)
+            ))
+        } else {
+            None
+        };
+        if let Some(reason) = skip_reason {
+            println!("⚠️ Sync skipped {}: {}", table, reason);
+            skipped_tables.push(SkippedTable {
+                table_name: table.clone(),
+                reason,
+            });
+            continue;
+        }
+
+        let ignored: Vec<bool> = common
+            .iter()
+            .map(|c| filter.ignores_column(table, c))
+            .collect();
+        let plan = SyncTable {
+            table,
+            key: &key,
+            columns: &common,
+            ignored: &ignored,
+            policy: options.policy,
+            rules: &options.rules,
+            updated_at,
+        };
+        let stats = plan
+            .sync(&source, &target, &mut to_source, &mut to_target)
+            .with_context(|| format!("Failed to sync table {}", table))?;
+        tables.push(stats);
+    }
+
+    for out in [&mut to_source, &mut to_target] {
+        writeln!(out, "COMMIT;")?;
+        out.flush()?;
+    }
+    Ok((tables, skipped_tables))
+}
+
+fn updated_at_column(options: &SyncOptions) -> &str {
+    options.updated_at_column.as_deref().unwrap_or("updated_at")
+}
+
+fn create_patch(path: &str) -> anyhow::Result<BufWriter<File>> {
+    Ok(BufWriter::new(
+        File::create(path).with_context(|| format!("Failed to create {}", path))?,
+    ))
+}
+
+fn write_header(
+    out: &mut impl Write,
+    direction: &str,
+    apply_to: &str,
+    policy: SyncConflictPolicy,
+) -> anyhow::Result<()> {
+    writeln!(out, "-- Data Synchronization Patch")?;
+    writeln!(out, "-- Direction: {}", direction)?;
+    writeln!(out, "-- Apply to: {}", apply_to)?;
+    writeln!(out, "-- Conflict policy: {:?}", policy)?;
+    writeln!(out, "-- Generated: {} UTC", chrono::Utc::now())?;
+    writeln!(out)?;
+    writeln!(out, "BEGIN TRANSACTION;\n")?;
+    Ok(())
+}
+
+struct SyncTable<'a> {
+    table: &'a str,
+    key: &'a [String],
+    /// Columns both sides have; the only ones that are compared and copied
+    columns: &'a [String],
+    /// Per column, whether the filter ignores it: copied with new rows, never compared
+    ignored: &'a [bool],
+    policy: SyncConflictPolicy,
+    rules: &'a ComparisonRules,
+    /// Position of the `newest` policy's timestamp column in `columns`
+    updated_at: Option<usize>,
+}
+
+impl SyncTable<'_> {
+    /// Walk both sides in key order at once
+    fn sync(
+        &self,
+        source: &Connection,
+        target: &Connection,
+        to_source: &mut impl Write,
+        to_target: &mut impl Write,
+    ) -> anyhow::Result<SyncTableStats> {
+        let mut stats = SyncTableStats {
+            table_name: self.table.to_string(),
+            ..Default::default()
+        };
+        let sql = self.select_sql();
+        let mut source_stmt = source.prepare(&sql)?;
+        let mut target_stmt = target.prepare(&sql)?;
+        let mut source_rows = source_stmt.query([])?;
+        let mut target_rows = target_stmt.query([])?;
+
+        let mut source_row = self.next_row(&mut source_rows)?;
+        let mut target_row = self.next_row(&mut target_rows)?;
+        loop {
+            let ordering = match (&source_row, &target_row) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(s), Some(t)) => compare_keys(&s[..self.key.len()], &t[..self.key.len()]),
+            };
+            match ordering {
+                Ordering::Less => {
+                    let row = source_row.take().unwrap_or_default();
+                    self.write_insert(to_target, &row)?;
+                    stats.inserted_into_target += 1;
+                    source_row = self.next_row(&mut source_rows)?;
+                }
+                Ordering::Greater => {
+                    let row = target_row.take().unwrap_or_default();
+                    self.write_insert(to_source, &row)?;
+                    stats.inserted_into_source += 1;
+                    target_row = self.next_row(&mut target_rows)?;
+                }
+                Ordering::Equal => {
+                    let s = source_row.take().unwrap_or_default();
+                    let t = target_row.take().unwrap_or_default();
+                    if !self.rows_equal(&s, &t) {
+                        match self.winner(&s, &t) {
+                            Some(Side::Source) => {
+                                self.write_update(to_target, &s, &t)?;
+                                stats.updated_in_target += 1;
+                            }
+                            Some(Side::Target) => {
+                                self.write_update(to_source, &t, &s)?;
+                                stats.updated_in_source += 1;
+                            }
+                            None => stats.unresolved += 1,
+                        }
+                    }
+                    source_row = self.next_row(&mut source_rows)?;
+                    target_row = self.next_row(&mut target_rows)?;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Key columns first, then every common column
+    fn select_sql(&self) -> String {
+        let select: Vec<String> = self
+            .key
+            .iter()
+            .chain(self.columns)
+            .map(|c| quote_identifier(c))
+            .collect();
+        let order_by: Vec<String> = self
+            .key
+            .iter()
+            .map(|k| format!("{} COLLATE BINARY", quote_identifier(k)))
+            .collect();
+        format!(
+            "SELECT {} FROM {} ORDER BY {}",
+            select.join(", "),
+            quote_identifier(self.table),
+            order_by.join(", ")
+        )
+    }
+
+    fn next_row(&self, rows: &mut Rows) -> anyhow::Result<Option<Vec<Value>>> {
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let values = (0..self.key.len() + self.columns.len())
+            .map(|i| row.get::<_, Value>(i))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(Some(values))
+    }
+
+    fn rows_equal(&self, source: &[Value], target: &[Value]) -> bool {
+        let offset = self.key.len();
+        (0..self.columns.len()).all(|i| {
+            self.ignored[i]
+                || self
+                    .rules
+                    .values_equal(&source[offset + i], &target[offset + i])
+        })
+    }
+
+    fn winner(&self, source: &[Value], target: &[Value]) -> Option<Side> {
+        match self.policy {
+            SyncConflictPolicy::SourceWins => Some(Side::Source),
+            SyncConflictPolicy::TargetWins => Some(Side::Target),
+            SyncConflictPolicy::Newest => {
+                let i = self.key.len() + self.updated_at?;
+                // Timestamps in different layouts are compared as instants
+                let ordering = compare_timestamps(&source[i], &target[i])
+                    .unwrap_or_else(|| compare_keys(&source[i..=i], &target[i..=i]));
+                match ordering {
+                    Ordering::Greater => Some(Side::Source),
+                    Ordering::Less => Some(Side::Target),
+                    Ordering::Equal => None,
+                }
+            }
+        }
+    }
+
+    fn write_insert(&self, out: &mut impl Write, row: &[Value]) -> anyhow::Result<()> {
+        let names: Vec<String> = self.columns.iter().map(|c| quote_identifier(c)).collect();
+        let values: Vec<String> = row[self.key.len()..].iter().map(sql_literal).collect();
+        writeln!(
+            out,
+            "INSERT INTO {} ({}) VALUES ({});",
+            quote_identifier(self.table),
+            names.join(", "),
+            values.join(", ")
+        )?;
+        Ok(())
+    }
+
+    /// Update the losing side's row to the winner's values
+    fn write_update(
+        &self,
+        out: &mut impl Write,
+        winner: &[Value],
+        loser: &[Value],
+    ) -> anyhow::Result<()> {
+        let offset = self.key.len();
+        let set_clauses: Vec<String> = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                !self.ignored[*i]
+                    && !self
+                        .rules
+                        .values_equal(&winner[offset + i], &loser[offset + i])
+            })
+            .map(|(i, c)| {
+                format!(
+                    "{} = {}",
+                    quote_identifier(c),
+                    sql_literal(&winner[offset + i])
+                )
+            })
+            .collect();
+        let where_clause: Vec<String> = self
+            .key
+            .iter()
+            .zip(loser)
+            .map(|(k, v)| format!("{} IS {}", quote_identifier(k), sql_literal(v)))
+            .collect();
+        writeln!(
+            out,
+            "UPDATE {} SET {} WHERE {};",
+            quote_identifier(self.table),
+            set_clauses.join(", "),
+            where_clause.join(" AND ")
+        )?;
+        Ok(())
+    }
+}
+
+enum Side {
+    Source,
+    Target,
+}
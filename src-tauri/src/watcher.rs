@@ -0,0 +1,39 @@
+// src-tauri/src/watcher.rs
+use crate::database::DatabaseManager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted to the frontend whenever a connected database changes on disk
+pub const DATABASE_CHANGED_EVENT: &str = "database-changed";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Start polling connected databases for external changes (mtime/size/data_version)
+pub fn start(app: AppHandle) {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        while RUNNING.load(Ordering::SeqCst) {
+            std::thread::sleep(POLL_INTERVAL);
+
+            // Databases busy with a long-running command are skipped until the next tick
+            let events = app.state::<DatabaseManager>().check_for_changes();
+
+            for event in events {
+                println!("📣 Database changed ({:?}): {}", event.kind, event.path);
+                if let Err(e) = app.emit(DATABASE_CHANGED_EVENT, &event) {
+                    println!("⚠️ Failed to emit {}: {}", DATABASE_CHANGED_EVENT, e);
+                }
+            }
+        }
+    });
+}
+
+pub fn stop() {
+    RUNNING.store(false, Ordering::SeqCst);
+}